@@ -0,0 +1,433 @@
+use std::{
+    fmt,
+    io::{self, Read, Write},
+};
+
+use ff::PrimeField;
+use halo2_proofs::{
+    pasta::{EqAffine, Fp},
+    plonk::{Circuit, Error},
+    poly::commitment::Params,
+};
+
+use crate::{
+    circuits::{CircuitId, CircuitVisitor, InvalidInputs},
+    proof::{self, MAX_K},
+};
+
+// A proof artifact is a self-describing file holding everything a verifier needs
+// besides the (re-derivable) keys. All integers are little-endian.
+//
+//   magic            4 bytes   "H2EX"
+//   version          u32       FORMAT_VERSION
+//   circuit id       u32 len + UTF-8, e.g. "fibonacci1:9"
+//   k                u32
+//   instance columns u32 count, then per column: u32 len + len * 32-byte field elements
+//   proof            u32 len + bytes
+//   checksum         32-byte Blake2b of everything above
+
+pub const MAGIC: [u8; 4] = *b"H2EX";
+pub const FORMAT_VERSION: u32 = 1;
+
+const CHECKSUM_LEN: usize = 32;
+const FIELD_LEN: usize = 32;
+
+#[derive(Debug)]
+pub enum ArtifactError {
+    Io(io::Error),
+    /// The input ended before the artifact was complete.
+    Truncated,
+    /// The input is not a proof artifact at all.
+    BadMagic,
+    UnsupportedVersion(u32),
+    /// The stored checksum does not match the content, i.e. the file was corrupted.
+    ChecksumMismatch,
+    UnknownCircuit(String),
+    /// `k` is above [`MAX_K`], setting up its parameters would exhaust memory or panic.
+    KTooLarge(u32),
+    InvalidInputs(InvalidInputs),
+    /// Creating the proof failed, e.g. because the inputs do not satisfy the circuit.
+    Proving(Error),
+    /// An instance value is not the canonical encoding of a field element.
+    InvalidFieldElement,
+    /// There are unparsed bytes between the proof and the checksum.
+    TrailingBytes,
+    /// Rebuilding the verifying key or checking the proof failed.
+    Verification(Error),
+}
+
+impl fmt::Display for ArtifactError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ArtifactError::Io(e) => write!(f, "I/O error: {e}"),
+            ArtifactError::Truncated => write!(f, "proof artifact is truncated"),
+            ArtifactError::BadMagic => write!(f, "not a proof artifact"),
+            ArtifactError::UnsupportedVersion(v) => {
+                write!(
+                    f,
+                    "unsupported artifact version {v} (expected {FORMAT_VERSION})"
+                )
+            }
+            ArtifactError::ChecksumMismatch => write!(f, "artifact checksum mismatch"),
+            ArtifactError::UnknownCircuit(id) => write!(f, "unknown circuit `{id}`"),
+            ArtifactError::KTooLarge(k) => {
+                write!(f, "k = {k} is above the supported maximum of {MAX_K}")
+            }
+            ArtifactError::InvalidInputs(e) => write!(f, "{e}"),
+            ArtifactError::Proving(e) => write!(f, "proving failed: {e}"),
+            ArtifactError::InvalidFieldElement => write!(f, "invalid field element in instance"),
+            ArtifactError::TrailingBytes => write!(f, "unexpected trailing bytes in artifact"),
+            ArtifactError::Verification(e) => write!(f, "proof verification failed: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for ArtifactError {}
+
+impl From<io::Error> for ArtifactError {
+    fn from(e: io::Error) -> Self {
+        ArtifactError::Io(e)
+    }
+}
+
+/// A proof together with the public data it was created for.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProofArtifact {
+    pub circuit: CircuitId,
+    pub k: u32,
+    /// One vector per instance column, e.g. `[[a, b, out]]` for Fibonacci.
+    pub instances: Vec<Vec<Fp>>,
+    pub proof: Vec<u8>,
+}
+
+impl ProofArtifact {
+    /// Proves `circuit` for `inputs` (its default inputs when empty) under `2^k` rows.
+    ///
+    /// The proof is checked before it is returned, so inputs that do not satisfy the
+    /// circuit are reported as [`ArtifactError::Proving`] instead of producing a
+    /// worthless artifact.
+    pub fn create(circuit: CircuitId, k: u32, inputs: &[u64]) -> Result<Self, ArtifactError> {
+        check_k(k)?;
+        let (instances, proof) = circuit
+            .with_circuit(inputs, Prove(k))
+            .map_err(ArtifactError::InvalidInputs)?
+            .map_err(ArtifactError::Proving)?;
+        Ok(ProofArtifact {
+            circuit,
+            k,
+            instances,
+            proof,
+        })
+    }
+
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_all(&self.to_bytes())
+    }
+
+    pub fn read<R: Read>(mut reader: R) -> Result<Self, ArtifactError> {
+        let mut bytes = vec![];
+        reader.read_to_end(&mut bytes)?;
+        Self::from_bytes(&bytes)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.encode(FORMAT_VERSION)
+    }
+
+    fn encode(&self, version: u32) -> Vec<u8> {
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend_from_slice(&version.to_le_bytes());
+
+        let circuit = self.circuit.to_string();
+        write_len(&mut bytes, circuit.len());
+        bytes.extend_from_slice(circuit.as_bytes());
+
+        bytes.extend_from_slice(&self.k.to_le_bytes());
+
+        write_len(&mut bytes, self.instances.len());
+        for column in &self.instances {
+            write_len(&mut bytes, column.len());
+            for value in column {
+                bytes.extend_from_slice(value.to_repr().as_ref());
+            }
+        }
+
+        write_len(&mut bytes, self.proof.len());
+        bytes.extend_from_slice(&self.proof);
+
+        let checksum = checksum(&bytes);
+        bytes.extend_from_slice(&checksum);
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ArtifactError> {
+        let mut header = Cursor(bytes);
+        if header.take(MAGIC.len())? != &MAGIC[..] {
+            return Err(ArtifactError::BadMagic);
+        }
+        let version = header.u32()?;
+        if version != FORMAT_VERSION {
+            return Err(ArtifactError::UnsupportedVersion(version));
+        }
+
+        if bytes.len() < CHECKSUM_LEN {
+            return Err(ArtifactError::Truncated);
+        }
+        let (content, stored) = bytes.split_at(bytes.len() - CHECKSUM_LEN);
+        if content.len() < MAGIC.len() + 4 {
+            return Err(ArtifactError::Truncated);
+        }
+        if checksum(content).as_slice() != stored {
+            return Err(ArtifactError::ChecksumMismatch);
+        }
+
+        let mut body = Cursor(&content[MAGIC.len() + 4..]);
+
+        let len = body.read_len()?;
+        let circuit = String::from_utf8_lossy(body.take(len)?)
+            .parse::<CircuitId>()
+            .map_err(|e| ArtifactError::UnknownCircuit(e.0))?;
+
+        let k = check_k(body.u32()?)?;
+
+        let num_columns = body.read_len()?;
+        let mut instances = vec![];
+        for _ in 0..num_columns {
+            let len = body.read_len()?;
+            let column = body
+                .take(len.checked_mul(FIELD_LEN).ok_or(ArtifactError::Truncated)?)?
+                .chunks(FIELD_LEN)
+                .map(|chunk| {
+                    let mut repr = <Fp as PrimeField>::Repr::default();
+                    repr.as_mut().copy_from_slice(chunk);
+                    Option::<Fp>::from(Fp::from_repr(repr))
+                        .ok_or(ArtifactError::InvalidFieldElement)
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            instances.push(column);
+        }
+
+        let len = body.read_len()?;
+        let proof = body.take(len)?.to_vec();
+
+        if !body.0.is_empty() {
+            return Err(ArtifactError::TrailingBytes);
+        }
+
+        Ok(ProofArtifact {
+            circuit,
+            k,
+            instances,
+            proof,
+        })
+    }
+}
+
+/// Rebuilds the verifying key for the artifact's circuit and checks the proof
+/// against the stored instance columns.
+pub fn verify_artifact(artifact: &ProofArtifact) -> Result<(), ArtifactError> {
+    let params = Params::<EqAffine>::new(check_k(artifact.k)?);
+    let vk = artifact
+        .circuit
+        .keygen_vk(&params)
+        .map_err(ArtifactError::Verification)?;
+    proof::verify(&params, &vk, &artifact.instances, &artifact.proof)
+        .map_err(ArtifactError::Verification)
+}
+
+fn check_k(k: u32) -> Result<u32, ArtifactError> {
+    if k > MAX_K {
+        return Err(ArtifactError::KTooLarge(k));
+    }
+    Ok(k)
+}
+
+struct Prove(u32);
+
+impl CircuitVisitor for Prove {
+    type Output = Result<(Vec<Vec<Fp>>, Vec<u8>), Error>;
+
+    fn visit<C: Circuit<Fp>>(self, circuit: C, instances: Vec<Vec<Fp>>) -> Self::Output {
+        let (params, pk) = proof::setup(self.0, &circuit)?;
+        let proof = proof::prove(&params, &pk, circuit, &instances)?;
+        proof::verify(&params, pk.get_vk(), &instances, &proof)?;
+        Ok((instances, proof))
+    }
+}
+
+fn write_len(bytes: &mut Vec<u8>, len: usize) {
+    let len = u32::try_from(len).expect("artifact sections are smaller than 4 GiB");
+    bytes.extend_from_slice(&len.to_le_bytes());
+}
+
+fn checksum(bytes: &[u8]) -> [u8; CHECKSUM_LEN] {
+    let hash = blake2b_simd::Params::new()
+        .hash_length(CHECKSUM_LEN)
+        .hash(bytes);
+    let mut checksum = [0; CHECKSUM_LEN];
+    checksum.copy_from_slice(hash.as_bytes());
+    checksum
+}
+
+struct Cursor<'a>(&'a [u8]);
+
+impl<'a> Cursor<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], ArtifactError> {
+        if self.0.len() < n {
+            return Err(ArtifactError::Truncated);
+        }
+        let (head, tail) = self.0.split_at(n);
+        self.0 = tail;
+        Ok(head)
+    }
+
+    fn u32(&mut self) -> Result<u32, ArtifactError> {
+        let mut le = [0; 4];
+        le.copy_from_slice(self.take(4)?);
+        Ok(u32::from_le_bytes(le))
+    }
+
+    fn read_len(&mut self) -> Result<usize, ArtifactError> {
+        self.u32().map(|len| len as usize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        fibonacci::example1::{nth_term, FibonacciCircuit},
+        proof::{prove, setup},
+        rows::min_k,
+    };
+
+    fn fibonacci_artifact(n: usize) -> ProofArtifact {
+        let circuit = FibonacciCircuit::<Fp>::new(n);
+        let k = min_k(&circuit).unwrap();
+        let (a, b) = (Fp::from(1), Fp::from(1));
+        let instances = vec![vec![a, b, nth_term(a, b, n)]];
+
+        let (params, pk) = setup(k, &circuit).unwrap();
+        let proof = prove(&params, &pk, circuit, &instances).unwrap();
+        ProofArtifact {
+            circuit: CircuitId::Fibonacci1 { n },
+            k,
+            instances,
+            proof,
+        }
+    }
+
+    #[test]
+    fn test_artifact_roundtrip() {
+        let artifact = fibonacci_artifact(9);
+
+        let mut file = vec![];
+        artifact.write(&mut file).unwrap();
+        let read = ProofArtifact::read(&file[..]).unwrap();
+
+        assert_eq!(read, artifact);
+        verify_artifact(&read).unwrap();
+    }
+
+    #[test]
+    fn test_artifact_create() {
+        for circuit in [CircuitId::Fibonacci2 { n: 12 }, CircuitId::RangeCheck1] {
+            let artifact = ProofArtifact::create(circuit, 5, &[]).unwrap();
+            let read = ProofArtifact::from_bytes(&artifact.to_bytes()).unwrap();
+            verify_artifact(&read).unwrap();
+        }
+
+        assert!(matches!(
+            ProofArtifact::create(CircuitId::RangeCheck1, 5, &[8]),
+            Err(ArtifactError::Proving(_))
+        ));
+        assert!(matches!(
+            ProofArtifact::create(CircuitId::RangeCheck1, 5, &[1, 2]),
+            Err(ArtifactError::InvalidInputs(_))
+        ));
+    }
+
+    #[test]
+    fn test_artifact_rejects_corruption() {
+        let artifact = fibonacci_artifact(9);
+        let bytes = artifact.to_bytes();
+
+        let mut corrupted = bytes.clone();
+        corrupted[20] ^= 1;
+        assert!(matches!(
+            ProofArtifact::from_bytes(&corrupted),
+            Err(ArtifactError::ChecksumMismatch)
+        ));
+
+        let mut not_an_artifact = bytes.clone();
+        not_an_artifact[0] = b'X';
+        assert!(matches!(
+            ProofArtifact::from_bytes(&not_an_artifact),
+            Err(ArtifactError::BadMagic)
+        ));
+
+        assert!(matches!(
+            ProofArtifact::from_bytes(&artifact.encode(FORMAT_VERSION + 1)),
+            Err(ArtifactError::UnsupportedVersion(v)) if v == FORMAT_VERSION + 1
+        ));
+
+        for len in [0, 3, 8, bytes.len() / 2, bytes.len() - 1] {
+            assert!(ProofArtifact::from_bytes(&bytes[..len]).is_err());
+        }
+        assert!(matches!(
+            ProofArtifact::from_bytes(&bytes[..6]),
+            Err(ArtifactError::Truncated)
+        ));
+    }
+
+    #[test]
+    fn test_artifact_rejects_mismatch() {
+        let artifact = fibonacci_artifact(9);
+
+        // a proof for f(9) does not verify as a proof for f(10)
+        let other_circuit = ProofArtifact {
+            circuit: CircuitId::Fibonacci1 { n: 10 },
+            ..artifact.clone()
+        };
+        assert!(matches!(
+            verify_artifact(&other_circuit),
+            Err(ArtifactError::Verification(_))
+        ));
+
+        let mut wrong_instance = artifact.clone();
+        wrong_instance.instances[0][2] += Fp::one();
+        assert!(matches!(
+            verify_artifact(&wrong_instance),
+            Err(ArtifactError::Verification(_))
+        ));
+
+        let other_k = ProofArtifact { k: 5, ..artifact };
+        assert!(matches!(
+            verify_artifact(&other_k),
+            Err(ArtifactError::Verification(_))
+        ));
+    }
+
+    #[test]
+    fn test_artifact_rejects_large_k() {
+        // a well-formed, correctly checksummed file is still refused before any setup
+        for k in [MAX_K + 1, 32, u32::MAX] {
+            let artifact = ProofArtifact {
+                k,
+                ..fibonacci_artifact(9)
+            };
+            assert!(matches!(
+                ProofArtifact::from_bytes(&artifact.to_bytes()),
+                Err(ArtifactError::KTooLarge(large)) if large == k
+            ));
+            assert!(matches!(
+                verify_artifact(&artifact),
+                Err(ArtifactError::KTooLarge(_))
+            ));
+        }
+        assert!(matches!(
+            ProofArtifact::create(CircuitId::RangeCheck1, MAX_K + 1, &[]),
+            Err(ArtifactError::KTooLarge(_))
+        ));
+    }
+}
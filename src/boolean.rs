@@ -0,0 +1,401 @@
+use std::{
+    marker::PhantomData,
+    ops::{Add, Mul, Sub},
+};
+
+use ff::PrimeField;
+use halo2_proofs::{circuit::*, plonk::*, poly::Rotation};
+
+// Boolean cells and logic gates over them:
+//
+//   x | y | out | q_bool | q_and | q_or | ...
+//
+// q_bool constrains x · (1 - x) = 0. Every operation row re-checks its inputs the
+// same way, so the outputs of other chips (e.g. `IsZeroChip` in output mode) can be
+// fed in directly, and the output is a bit by construction.
+
+/// `value · (1 - value)`, zero exactly when `value` is 0 or 1.
+pub fn bool_check<F: PrimeField>(value: Expression<F>) -> Expression<F> {
+    value.clone() * (Expression::Constant(F::ONE) - value)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BoolOp {
+    And,
+    Or,
+    Xor,
+    Nand,
+    Implies,
+    Not,
+}
+
+impl BoolOp {
+    pub const ALL: [BoolOp; 6] = [
+        BoolOp::And,
+        BoolOp::Or,
+        BoolOp::Xor,
+        BoolOp::Nand,
+        BoolOp::Implies,
+        BoolOp::Not,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            BoolOp::And => "and",
+            BoolOp::Or => "or",
+            BoolOp::Xor => "xor",
+            BoolOp::Nand => "nand",
+            BoolOp::Implies => "implies",
+            BoolOp::Not => "not",
+        }
+    }
+
+    fn is_unary(self) -> bool {
+        self == BoolOp::Not
+    }
+
+    // the arithmetization on bits, shared by the gates and the witness
+    fn eval<T>(self, one: T, x: T, y: T) -> T
+    where
+        T: Clone + Add<Output = T> + Sub<Output = T> + Mul<Output = T>,
+    {
+        let xy = x.clone() * y.clone();
+        match self {
+            BoolOp::And => xy,
+            BoolOp::Or => x + y - xy,
+            BoolOp::Xor => x + y - xy.clone() - xy,
+            BoolOp::Nand => one - xy,
+            BoolOp::Implies => one - x + xy,
+            BoolOp::Not => one - x,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BoolConfig {
+    x: Column<Advice>,
+    y: Column<Advice>,
+    out: Column<Advice>,
+    q_bool: Selector,
+    // indexed like `BoolOp::ALL`
+    q_ops: [Selector; 6],
+}
+
+pub struct BoolChip<F: PrimeField> {
+    config: BoolConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField> BoolChip<F> {
+    pub fn construct(config: BoolConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        [x, y, out]: [Column<Advice>; 3],
+    ) -> BoolConfig {
+        for column in [x, y, out] {
+            meta.enable_equality(column);
+        }
+
+        let q_bool = meta.selector();
+        meta.create_gate("bool", |meta| {
+            let q = meta.query_selector(q_bool);
+            let x = meta.query_advice(x, Rotation::cur());
+            Constraints::with_selector(q, [("x is boolean", bool_check(x))])
+        });
+
+        let q_ops = BoolOp::ALL.map(|op| {
+            let q_op = meta.selector();
+            meta.create_gate(op.name(), |meta| {
+                let q = meta.query_selector(q_op);
+                let x = meta.query_advice(x, Rotation::cur());
+                let out = meta.query_advice(out, Rotation::cur());
+
+                let mut constraints = vec![bool_check(x.clone())];
+                // unary rows leave y unassigned, so it is not queried at all
+                let y = if op.is_unary() {
+                    Expression::Constant(F::ZERO)
+                } else {
+                    let y = meta.query_advice(y, Rotation::cur());
+                    constraints.push(bool_check(y.clone()));
+                    y
+                };
+                constraints.push(out - op.eval(Expression::Constant(F::ONE), x, y));
+                Constraints::with_selector(q, constraints)
+            });
+            q_op
+        });
+
+        BoolConfig {
+            x,
+            y,
+            out,
+            q_bool,
+            q_ops,
+        }
+    }
+
+    /// Witnesses a new bit.
+    pub fn assign_bool(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: Value<bool>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        layouter.assign_region(
+            || "bool",
+            |mut region| {
+                self.config.q_bool.enable(&mut region, 0)?;
+                let value = value.map(|b| F::from(b as u64));
+                region.assign_advice(|| "x", self.config.x, 0, || value)
+            },
+        )
+    }
+
+    /// Constrains an already assigned cell to be a bit.
+    pub fn assert_bool(
+        &self,
+        mut layouter: impl Layouter<F>,
+        cell: &AssignedCell<F, F>,
+    ) -> Result<(), Error> {
+        layouter.assign_region(
+            || "assert bool",
+            |mut region| {
+                self.config.q_bool.enable(&mut region, 0)?;
+                cell.copy_advice(|| "x", &mut region, self.config.x, 0)?;
+                Ok(())
+            },
+        )
+    }
+
+    /// Applies `op` to `x` (and `y` for binary operations) in a new row.
+    pub fn apply(
+        &self,
+        mut layouter: impl Layouter<F>,
+        op: BoolOp,
+        x: &AssignedCell<F, F>,
+        y: Option<&AssignedCell<F, F>>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        assert_eq!(
+            op.is_unary(),
+            y.is_none(),
+            "wrong number of operands for {op:?}"
+        );
+        let config = &self.config;
+        let index = BoolOp::ALL.iter().position(|&o| o == op).unwrap();
+
+        layouter.assign_region(
+            || op.name(),
+            |mut region| {
+                config.q_ops[index].enable(&mut region, 0)?;
+                let x = x.copy_advice(|| "x", &mut region, config.x, 0)?;
+                let y = match y {
+                    Some(y) => y
+                        .copy_advice(|| "y", &mut region, config.y, 0)?
+                        .value()
+                        .copied(),
+                    None => Value::known(F::ZERO),
+                };
+
+                let out = op.eval(Value::known(F::ONE), x.value().copied(), y);
+                region.assign_advice(|| "out", config.out, 0, || out)
+            },
+        )
+    }
+
+    pub fn and(
+        &self,
+        layouter: impl Layouter<F>,
+        x: &AssignedCell<F, F>,
+        y: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        self.apply(layouter, BoolOp::And, x, Some(y))
+    }
+
+    pub fn or(
+        &self,
+        layouter: impl Layouter<F>,
+        x: &AssignedCell<F, F>,
+        y: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        self.apply(layouter, BoolOp::Or, x, Some(y))
+    }
+
+    pub fn xor(
+        &self,
+        layouter: impl Layouter<F>,
+        x: &AssignedCell<F, F>,
+        y: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        self.apply(layouter, BoolOp::Xor, x, Some(y))
+    }
+
+    pub fn nand(
+        &self,
+        layouter: impl Layouter<F>,
+        x: &AssignedCell<F, F>,
+        y: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        self.apply(layouter, BoolOp::Nand, x, Some(y))
+    }
+
+    /// `x → y`, i.e. `!x || y`.
+    pub fn implies(
+        &self,
+        layouter: impl Layouter<F>,
+        x: &AssignedCell<F, F>,
+        y: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        self.apply(layouter, BoolOp::Implies, x, Some(y))
+    }
+
+    pub fn not(
+        &self,
+        layouter: impl Layouter<F>,
+        x: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        self.apply(layouter, BoolOp::Not, x, None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        is_zero::{IsZeroChip, IsZeroConfig},
+        rows::{assert_bits_bound, mock_verify},
+    };
+    use halo2_proofs::pasta::Fp;
+
+    #[derive(Clone, Debug)]
+    struct TestConfig<F: PrimeField> {
+        boolean: BoolConfig,
+        q_value: Selector,
+        value: Column<Advice>,
+        is_zero: IsZeroConfig<F>,
+        instance: Column<Instance>,
+    }
+
+    // `raw` is only asserted to be a bit.
+    // instance: [x & y, x | y, x ^ y, !(x & y), x → y, !x, (value == 0) & y]
+    #[derive(Default)]
+    struct BoolCircuit<F> {
+        x: Value<bool>,
+        y: Value<bool>,
+        value: Value<F>,
+        raw: Value<F>,
+    }
+
+    impl<F: PrimeField> Circuit<F> for BoolCircuit<F> {
+        type Config = TestConfig<F>;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let columns = [(); 3].map(|_| meta.advice_column());
+            let boolean = BoolChip::configure(meta, columns);
+            let q_value = meta.selector();
+            let [value, value_inv, is_zero_out] = [(); 3].map(|_| meta.advice_column());
+            let instance = meta.instance_column();
+            meta.enable_equality(value);
+            meta.enable_equality(instance);
+
+            let is_zero = IsZeroChip::configure_with_output(
+                meta,
+                |meta| meta.query_selector(q_value),
+                |meta| meta.query_advice(value, Rotation::cur()),
+                value_inv,
+                is_zero_out,
+            );
+
+            TestConfig {
+                boolean,
+                q_value,
+                value,
+                is_zero,
+                instance,
+            }
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let chip = BoolChip::construct(config.boolean);
+            let is_zero_chip = IsZeroChip::construct(config.is_zero);
+
+            let x = chip.assign_bool(layouter.namespace(|| "x"), self.x)?;
+            let y = chip.assign_bool(layouter.namespace(|| "y"), self.y)?;
+            let (is_zero, raw) = layouter.assign_region(
+                || "value",
+                |mut region| {
+                    config.q_value.enable(&mut region, 0)?;
+                    region.assign_advice(|| "value", config.value, 0, || self.value)?;
+                    let is_zero = is_zero_chip.assign(&mut region, 0, self.value)?;
+                    let raw = region.assign_advice(|| "raw", config.value, 1, || self.raw)?;
+                    Ok((is_zero.unwrap(), raw))
+                },
+            )?;
+            chip.assert_bool(layouter.namespace(|| "raw"), &raw)?;
+
+            let outputs = [
+                chip.and(layouter.namespace(|| "and"), &x, &y)?,
+                chip.or(layouter.namespace(|| "or"), &x, &y)?,
+                chip.xor(layouter.namespace(|| "xor"), &x, &y)?,
+                chip.nand(layouter.namespace(|| "nand"), &x, &y)?,
+                chip.implies(layouter.namespace(|| "implies"), &x, &y)?,
+                chip.not(layouter.namespace(|| "not"), &x)?,
+                chip.and(layouter.namespace(|| "is zero and"), &is_zero, &y)?,
+            ];
+            for (row, cell) in outputs.iter().enumerate() {
+                layouter.constrain_instance(cell.cell(), config.instance, row)?;
+            }
+            Ok(())
+        }
+    }
+
+    fn circuit(x: bool, y: bool, value: u64, raw: u64) -> BoolCircuit<Fp> {
+        BoolCircuit {
+            x: Value::known(x),
+            y: Value::known(y),
+            value: Value::known(Fp::from(value)),
+            raw: Value::known(Fp::from(raw)),
+        }
+    }
+
+    fn truth_table(x: bool, y: bool, value: u64) -> Vec<Fp> {
+        [x & y, x | y, x ^ y, !(x & y), !x | y, !x, (value == 0) & y]
+            .map(|b| Fp::from(b as u64))
+            .to_vec()
+    }
+
+    #[test]
+    fn test_bool_ops() {
+        for (x, y) in [(false, false), (false, true), (true, false), (true, true)] {
+            for value in [0, 3] {
+                assert_bits_bound(&circuit(x, y, value, 1), &truth_table(x, y, value));
+            }
+        }
+    }
+
+    #[test]
+    fn test_bool_rejects_non_bits() {
+        let instance = truth_table(true, false, 3);
+
+        for raw in [2, 5] {
+            let circuit = circuit(true, false, 3, raw);
+            assert!(
+                !mock_verify(&circuit, vec![instance.clone()]),
+                "raw = {raw}"
+            );
+        }
+    }
+}
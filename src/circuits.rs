@@ -0,0 +1,366 @@
+use std::{fmt, str::FromStr};
+
+use halo2_proofs::{
+    circuit::Value,
+    pasta::{EqAffine, Fp},
+    plonk::{keygen_vk, Circuit, Error, VerifyingKey},
+    poly::commitment::Params,
+};
+
+use crate::{
+    fibonacci::{
+        example1,
+        example1::nth_term,
+        example2,
+        example3::{function, FunctionCircuit},
+    },
+    range_check::{
+        decompose_range_check::DecomposeRangeCheckCircuit, example1 as range_check1,
+        example1b as range_check1b, example2 as range_check2, example3 as range_check3,
+    },
+    rows::{self, KError},
+};
+
+/// Names one of the example circuits together with the parameters that fix its shape,
+/// i.e. everything needed to rebuild its verifying key.
+///
+/// The textual form is `<name>` or `<name>:<parameter>`, e.g. `fibonacci1:9`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitId {
+    /// `fibonacci::example1::FibonacciCircuit` proving `f(n)`.
+    Fibonacci1 { n: usize },
+    /// `fibonacci::example2::FibonacciCircuit` proving `f(n)`.
+    Fibonacci2 { n: usize },
+    /// `fibonacci::example3::FunctionCircuit` with the public output `f(a, b, c)`.
+    Function,
+    /// `range_check::example1::RangeCheckCircuit` with `RANGE = 8`.
+    RangeCheck1,
+    /// `range_check::example1b::MyCircuit` with `RANGE = 8`.
+    RangeCheck1b,
+    /// `range_check::example2::RangeCheckCircuit` with `RANGE = 8`, `LOOKUP_RANGE = 256`.
+    RangeCheck2,
+    /// `range_check::example3::RangeCheckCircuit` with `NUM_BITS = 8`, `RANGE = 256`.
+    RangeCheck3,
+    /// `range_check::decompose_range_check::DecomposeRangeCheckCircuit`.
+    DecomposeRangeCheck,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownCircuit(pub String);
+
+impl fmt::Display for UnknownCircuit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown circuit `{}`", self.0)
+    }
+}
+
+impl std::error::Error for UnknownCircuit {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidInputs {
+    pub circuit: CircuitId,
+    pub found: usize,
+}
+
+impl fmt::Display for InvalidInputs {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let expected = self.circuit.input_names();
+        write!(
+            f,
+            "`{}` expects {} inputs ({}), found {}",
+            self.circuit,
+            expected.len(),
+            expected.join(", "),
+            self.found
+        )
+    }
+}
+
+impl std::error::Error for InvalidInputs {}
+
+/// Receives the concrete circuit picked by [`CircuitId::with_circuit`], so generic code
+/// (mock proving, keygen, proving, layout) can run on a circuit chosen at runtime.
+pub trait CircuitVisitor {
+    type Output;
+
+    fn visit<C: Circuit<Fp>>(self, circuit: C, instances: Vec<Vec<Fp>>) -> Self::Output;
+}
+
+impl CircuitId {
+    /// Every example circuit with its default parameters.
+    pub fn examples() -> Vec<CircuitId> {
+        vec![
+            CircuitId::Fibonacci1 { n: 9 },
+            CircuitId::Fibonacci2 { n: 9 },
+            CircuitId::Function,
+            CircuitId::RangeCheck1,
+            CircuitId::RangeCheck1b,
+            CircuitId::RangeCheck2,
+            CircuitId::RangeCheck3,
+            CircuitId::DecomposeRangeCheck,
+        ]
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            CircuitId::Fibonacci1 { .. } => "f(n) with one row per step (`:n` sets n)",
+            CircuitId::Fibonacci2 { .. } => "f(n) in a single advice column (`:n` sets n, n >= 3)",
+            CircuitId::Function => "f(a, b, c) = if a == b {c} else {a - b}",
+            CircuitId::RangeCheck1 => "value in 0..8 with a range-check polynomial",
+            CircuitId::RangeCheck1b => "value in 0..8, annotated variant of range-check1",
+            CircuitId::RangeCheck2 => "simple_value in 0..8 and lookup_value in 0..256",
+            CircuitId::RangeCheck3 => "value in 0..256 with a num_bits tagged lookup table",
+            CircuitId::DecomposeRangeCheck => "value in 0..64 decomposed into 3-bit chunks",
+        }
+    }
+
+    /// Names of the private or public inputs accepted by [`CircuitId::with_circuit`].
+    pub fn input_names(&self) -> &'static [&'static str] {
+        match self {
+            CircuitId::Fibonacci1 { .. } | CircuitId::Fibonacci2 { .. } => &["a", "b"],
+            CircuitId::Function => &["a", "b", "c"],
+            CircuitId::RangeCheck2 => &["simple_value", "lookup_value"],
+            CircuitId::RangeCheck1
+            | CircuitId::RangeCheck1b
+            | CircuitId::RangeCheck3
+            | CircuitId::DecomposeRangeCheck => &["value"],
+        }
+    }
+
+    pub fn default_inputs(&self) -> &'static [u64] {
+        match self {
+            CircuitId::Fibonacci1 { .. } | CircuitId::Fibonacci2 { .. } => &[1, 1],
+            CircuitId::Function => &[10, 12, 15],
+            CircuitId::RangeCheck1 => &[3],
+            CircuitId::RangeCheck1b => &[5],
+            CircuitId::RangeCheck2 => &[3, 200],
+            CircuitId::RangeCheck3 => &[20],
+            CircuitId::DecomposeRangeCheck => &[0],
+        }
+    }
+
+    /// The smallest `k` the circuit fits into, see [`rows::min_k`](crate::rows::min_k).
+    pub fn min_k(&self) -> Result<u32, KError> {
+        self.with_circuit(&[], MinK)
+            .expect("default inputs match the circuit")
+    }
+
+    /// Builds the circuit and its instance columns from `inputs` (or from the
+    /// [default inputs](CircuitId::default_inputs) when `inputs` is empty) and hands
+    /// them to `visitor`.
+    pub fn with_circuit<V: CircuitVisitor>(
+        &self,
+        inputs: &[u64],
+        visitor: V,
+    ) -> Result<V::Output, InvalidInputs> {
+        let inputs = if inputs.is_empty() {
+            self.default_inputs()
+        } else {
+            inputs
+        };
+        if inputs.len() != self.input_names().len() {
+            return Err(InvalidInputs {
+                circuit: *self,
+                found: inputs.len(),
+            });
+        }
+        let fp = |i: usize| Fp::from(inputs[i]);
+        let value = |i: usize| Value::known(fp(i).into());
+
+        Ok(match *self {
+            CircuitId::Fibonacci1 { n } => visitor.visit(
+                example1::FibonacciCircuit::new(n),
+                vec![vec![fp(0), fp(1), nth_term(fp(0), fp(1), n)]],
+            ),
+            CircuitId::Fibonacci2 { n } => visitor.visit(
+                example2::FibonacciCircuit::new(n),
+                vec![vec![fp(0), fp(1), nth_term(fp(0), fp(1), n)]],
+            ),
+            CircuitId::Function => visitor.visit(
+                FunctionCircuit {
+                    a: value(0),
+                    b: value(1),
+                    c: value(2),
+                },
+                vec![vec![function(fp(0), fp(1), fp(2))]],
+            ),
+            CircuitId::RangeCheck1 => visitor.visit(
+                range_check1::RangeCheckCircuit::<Fp, 8> { value: value(0) },
+                vec![],
+            ),
+            CircuitId::RangeCheck1b => {
+                visitor.visit(range_check1b::MyCircuit::<Fp, 8>::new(value(0)), vec![])
+            }
+            CircuitId::RangeCheck2 => visitor.visit(
+                range_check2::RangeCheckCircuit::<Fp, 8, 256> {
+                    simple_value: value(0),
+                    lookup_value: value(1),
+                },
+                vec![],
+            ),
+            CircuitId::RangeCheck3 => {
+                // tag the value with its bit length, 0 counts as a 1-bit value
+                let num_bits = (u64::BITS - inputs[0].leading_zeros()).max(1);
+                visitor.visit(
+                    range_check3::RangeCheckCircuit::<Fp, 8, 256> {
+                        num_bits: Value::known(num_bits as u8),
+                        value: value(0),
+                    },
+                    vec![],
+                )
+            }
+            CircuitId::DecomposeRangeCheck => visitor.visit(
+                DecomposeRangeCheckCircuit::<Fp>::new(inputs[0] as u128),
+                vec![],
+            ),
+        })
+    }
+
+    /// Rebuilds the verifying key of the circuit under `params`.
+    pub fn keygen_vk(&self, params: &Params<EqAffine>) -> Result<VerifyingKey<EqAffine>, Error> {
+        self.with_circuit(&[], KeygenVk(params))
+            .expect("default inputs match the circuit")
+    }
+}
+
+struct MinK;
+
+impl CircuitVisitor for MinK {
+    type Output = Result<u32, KError>;
+
+    fn visit<C: Circuit<Fp>>(self, circuit: C, _: Vec<Vec<Fp>>) -> Self::Output {
+        rows::min_k(&circuit)
+    }
+}
+
+struct KeygenVk<'a>(&'a Params<EqAffine>);
+
+impl CircuitVisitor for KeygenVk<'_> {
+    type Output = Result<VerifyingKey<EqAffine>, Error>;
+
+    fn visit<C: Circuit<Fp>>(self, circuit: C, _: Vec<Vec<Fp>>) -> Self::Output {
+        keygen_vk(self.0, &circuit.without_witnesses())
+    }
+}
+
+impl fmt::Display for CircuitId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CircuitId::Fibonacci1 { n } => write!(f, "fibonacci1:{n}"),
+            CircuitId::Fibonacci2 { n } => write!(f, "fibonacci2:{n}"),
+            CircuitId::Function => write!(f, "function"),
+            CircuitId::RangeCheck1 => write!(f, "range-check1"),
+            CircuitId::RangeCheck1b => write!(f, "range-check1b"),
+            CircuitId::RangeCheck2 => write!(f, "range-check2"),
+            CircuitId::RangeCheck3 => write!(f, "range-check3"),
+            CircuitId::DecomposeRangeCheck => write!(f, "decompose-range-check"),
+        }
+    }
+}
+
+impl FromStr for CircuitId {
+    type Err = UnknownCircuit;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let unknown = || UnknownCircuit(s.to_string());
+        let (name, param) = match s.split_once(':') {
+            Some((name, param)) => (name, Some(param.parse::<usize>().map_err(|_| unknown())?)),
+            None => (s, None),
+        };
+
+        match (name, param) {
+            // the 10-row sequence of the original examples: f(9) = 55
+            ("fibonacci1", n) => Ok(CircuitId::Fibonacci1 { n: n.unwrap_or(9) }),
+            ("fibonacci2", n) => Ok(CircuitId::Fibonacci2 { n: n.unwrap_or(9) }),
+            ("function", None) => Ok(CircuitId::Function),
+            ("range-check1", None) => Ok(CircuitId::RangeCheck1),
+            ("range-check1b", None) => Ok(CircuitId::RangeCheck1b),
+            ("range-check2", None) => Ok(CircuitId::RangeCheck2),
+            ("range-check3", None) => Ok(CircuitId::RangeCheck3),
+            ("decompose-range-check", None) => Ok(CircuitId::DecomposeRangeCheck),
+            _ => Err(unknown()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use halo2_proofs::dev::{MockProver, VerifyFailure};
+
+    struct Mock(u32);
+
+    impl CircuitVisitor for Mock {
+        type Output = Result<(), Vec<VerifyFailure>>;
+
+        fn visit<C: Circuit<Fp>>(self, circuit: C, instances: Vec<Vec<Fp>>) -> Self::Output {
+            MockProver::run(self.0, &circuit, instances)
+                .unwrap()
+                .verify()
+        }
+    }
+
+    #[test]
+    fn test_circuit_id_roundtrip() {
+        for id in CircuitId::examples() {
+            assert_eq!(id.to_string().parse::<CircuitId>(), Ok(id));
+        }
+        let id = CircuitId::Fibonacci1 { n: 20 };
+        assert_eq!(id.to_string().parse::<CircuitId>(), Ok(id));
+        assert_eq!("fibonacci1".parse(), Ok(CircuitId::Fibonacci1 { n: 9 }));
+
+        for bad in [
+            "",
+            "fibonacci1:",
+            "fibonacci1:x",
+            "fibonacci7",
+            "function:3",
+        ] {
+            assert_eq!(
+                bad.parse::<CircuitId>(),
+                Err(UnknownCircuit(bad.to_string()))
+            );
+        }
+    }
+
+    #[test]
+    fn test_examples_with_default_inputs() {
+        for id in CircuitId::examples() {
+            let k = id.min_k().unwrap();
+            assert_eq!(id.with_circuit(&[], Mock(k)), Ok(Ok(())), "{id}");
+        }
+    }
+
+    #[test]
+    fn test_examples_min_k() {
+        assert_eq!(CircuitId::Fibonacci1 { n: 9 }.min_k().unwrap(), 4);
+        assert_eq!(CircuitId::Fibonacci1 { n: 100 }.min_k().unwrap(), 7);
+        assert_eq!(CircuitId::RangeCheck1.min_k().unwrap(), 3);
+        assert_eq!(CircuitId::RangeCheck2.min_k().unwrap(), 9);
+        assert!(matches!(
+            CircuitId::Fibonacci2 { n: 2 }.min_k(),
+            Err(KError::Synthesis(Error::Synthesis))
+        ));
+    }
+
+    #[test]
+    fn test_examples_with_inputs() {
+        let id = CircuitId::Fibonacci1 { n: 20 };
+        assert_eq!(
+            id.with_circuit(&[2, 1], Mock(id.min_k().unwrap())),
+            Ok(Ok(()))
+        );
+
+        let id = CircuitId::RangeCheck2;
+        let k = id.min_k().unwrap();
+        assert!(matches!(id.with_circuit(&[3, 256], Mock(k)), Ok(Err(_))));
+
+        assert_eq!(
+            id.with_circuit(&[3], Mock(k)),
+            Err(InvalidInputs {
+                circuit: id,
+                found: 1
+            })
+        );
+    }
+}
@@ -0,0 +1,454 @@
+use ff::{Field, PrimeField};
+use halo2_proofs::{circuit::*, plonk::*, poly::Rotation};
+
+use crate::{boolean::bool_check, range_check::example2::table::RangeTableConfig};
+
+// a < b for a, b in [0, 2^(8·N_BYTES)).
+//
+// a - b + 2^(8N) has bit 8N set exactly when a >= b, so with a boolean lt
+//
+//   diff = a - b + lt · 2^(8N)
+//
+// lies in [0, 2^(8N)) only for lt = (a < b). diff is decomposed into N bytes, each
+// looked up in a shared 8-bit table. The same row also holds ge, min and max:
+//
+//   a | b | lt | ge | min | max | byte_0 | ... | byte_{N-1} | q_lt
+//
+//   lt · (1 - lt)         = 0
+//   Σ byte_i · 256^i      = a - b + lt · 2^(8N)
+//   ge                    = 1 - lt
+//   min                   = b + lt · (a - b)
+//   max                   = a + b - min
+//
+// The inputs are assumed to be in range already: for a or b >= 2^(8N) the result
+// is meaningless or the row is unprovable.
+
+pub const BYTE_RANGE: usize = 256;
+
+#[derive(Debug, Clone)]
+pub struct LtConfig<F: PrimeField, const N_BYTES: usize> {
+    q_lt: Selector,
+    a: Column<Advice>,
+    b: Column<Advice>,
+    lt: Column<Advice>,
+    ge: Column<Advice>,
+    min: Column<Advice>,
+    max: Column<Advice>,
+    bytes: [Column<Advice>; N_BYTES],
+    table: RangeTableConfig<F, BYTE_RANGE>,
+}
+
+/// The output cells of one comparator row.
+#[derive(Debug, Clone)]
+pub struct Comparison<F: PrimeField> {
+    pub lt: AssignedCell<F, F>,
+    pub ge: AssignedCell<F, F>,
+    pub min: AssignedCell<F, F>,
+    pub max: AssignedCell<F, F>,
+}
+
+pub struct LtChip<F: PrimeField, const N_BYTES: usize> {
+    config: LtConfig<F, N_BYTES>,
+}
+
+impl<F: PrimeField, const N_BYTES: usize> LtChip<F, N_BYTES> {
+    pub fn construct(config: LtConfig<F, N_BYTES>) -> Self {
+        Self { config }
+    }
+
+    /// `table` is only configured here, the circuit owning it loads it once.
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        [a, b, lt, ge, min, max]: [Column<Advice>; 6],
+        bytes: [Column<Advice>; N_BYTES],
+        table: RangeTableConfig<F, BYTE_RANGE>,
+    ) -> LtConfig<F, N_BYTES> {
+        assert!(
+            N_BYTES > 0 && 8 * N_BYTES < F::CAPACITY as usize,
+            "2^(8·N_BYTES) must fit in the field"
+        );
+        let q_lt = meta.complex_selector();
+        for column in [a, b, lt, ge, min, max] {
+            meta.enable_equality(column);
+        }
+
+        for byte in bytes {
+            meta.lookup(|meta| {
+                let q = meta.query_selector(q_lt);
+                let byte = meta.query_advice(byte, Rotation::cur());
+                vec![(q * byte, table.value)]
+            });
+        }
+
+        meta.create_gate("a < b", |meta| {
+            let q = meta.query_selector(q_lt);
+            let [a, b, lt, ge, min, max] =
+                [a, b, lt, ge, min, max].map(|column| meta.query_advice(column, Rotation::cur()));
+            let diff = bytes
+                .iter()
+                .rev()
+                .fold(Expression::Constant(F::ZERO), |acc, &byte| {
+                    acc * Expression::Constant(F::from(BYTE_RANGE as u64))
+                        + meta.query_advice(byte, Rotation::cur())
+                });
+            let one = Expression::Constant(F::ONE);
+
+            Constraints::with_selector(
+                q,
+                [
+                    ("lt is boolean", bool_check(lt.clone())),
+                    (
+                        "diff",
+                        a.clone() - b.clone() + lt.clone() * Expression::Constant(Self::range())
+                            - diff,
+                    ),
+                    ("ge", ge - (one - lt.clone())),
+                    (
+                        "min",
+                        min.clone() - (b.clone() + lt * (a.clone() - b.clone())),
+                    ),
+                    ("max", max - (a + b - min)),
+                ],
+            )
+        });
+
+        LtConfig {
+            q_lt,
+            a,
+            b,
+            lt,
+            ge,
+            min,
+            max,
+            bytes,
+            table,
+        }
+    }
+
+    // 2^(8·N_BYTES)
+    fn range() -> F {
+        F::from(BYTE_RANGE as u64).pow([N_BYTES as u64])
+    }
+
+    /// Copies `a` and `b` into a new comparator row.
+    pub fn compare(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &AssignedCell<F, F>,
+        b: &AssignedCell<F, F>,
+    ) -> Result<Comparison<F>, Error> {
+        let config = &self.config;
+
+        layouter.assign_region(
+            || "compare",
+            |mut region| {
+                config.q_lt.enable(&mut region, 0)?;
+                let a = a.copy_advice(|| "a", &mut region, config.a, 0)?;
+                let b = b.copy_advice(|| "b", &mut region, config.b, 0)?;
+                let (a, b) = (a.value().copied(), b.value().copied());
+
+                // the low N bytes of a - b + 2^(8N) are the bytes of diff in both cases,
+                // byte N holds bit 8N
+                let shifted = (a - b).map(|diff| le_bytes(diff + Self::range()));
+                for (i, &column) in config.bytes.iter().enumerate() {
+                    let byte = shifted.as_ref().map(|bytes| F::from(bytes[i] as u64));
+                    region.assign_advice(|| format!("byte {i}"), column, 0, || byte)?;
+                }
+                let lt = shifted.as_ref().map(|bytes| {
+                    if bytes[N_BYTES] & 1 == 1 {
+                        F::ZERO
+                    } else {
+                        F::ONE
+                    }
+                });
+
+                let lt = region.assign_advice(|| "lt", config.lt, 0, || lt)?;
+                let ge = region.assign_advice(
+                    || "ge",
+                    config.ge,
+                    0,
+                    || Value::known(F::ONE) - lt.value(),
+                )?;
+                let min_value = b + lt.value().copied() * (a - b);
+                let min = region.assign_advice(|| "min", config.min, 0, || min_value)?;
+                let max = region.assign_advice(|| "max", config.max, 0, || a + b - min_value)?;
+
+                Ok(Comparison { lt, ge, min, max })
+            },
+        )
+    }
+
+    /// 1 if `a < b`, 0 otherwise.
+    pub fn lt(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &AssignedCell<F, F>,
+        b: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        Ok(self.compare(layouter, a, b)?.lt)
+    }
+
+    /// 1 if `a <= b`, 0 otherwise.
+    pub fn le(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &AssignedCell<F, F>,
+        b: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        Ok(self.compare(layouter, b, a)?.ge)
+    }
+
+    /// 1 if `a > b`, 0 otherwise.
+    pub fn gt(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &AssignedCell<F, F>,
+        b: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        Ok(self.compare(layouter, b, a)?.lt)
+    }
+
+    /// 1 if `a >= b`, 0 otherwise.
+    pub fn ge(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &AssignedCell<F, F>,
+        b: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        Ok(self.compare(layouter, a, b)?.ge)
+    }
+
+    pub fn min(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &AssignedCell<F, F>,
+        b: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        Ok(self.compare(layouter, a, b)?.min)
+    }
+
+    pub fn max(
+        &self,
+        layouter: impl Layouter<F>,
+        a: &AssignedCell<F, F>,
+        b: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        Ok(self.compare(layouter, a, b)?.max)
+    }
+
+    /// `min(max(x, lo), hi)`, i.e. `x` clamped into `[lo, hi]` when `lo <= hi`.
+    pub fn clamp(
+        &self,
+        mut layouter: impl Layouter<F>,
+        x: &AssignedCell<F, F>,
+        lo: &AssignedCell<F, F>,
+        hi: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let at_least_lo = self.max(layouter.namespace(|| "max(x, lo)"), x, lo)?;
+        self.min(layouter.namespace(|| "min(_, hi)"), &at_least_lo, hi)
+    }
+}
+
+// pasta reprs are little-endian
+fn le_bytes<F: PrimeField>(value: F) -> Vec<u8> {
+    let repr = value.to_repr();
+    let bytes: &[u8] = repr.as_ref();
+    bytes.to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rows::assert_instance_bound;
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    fn configure_lt<F: PrimeField, const N: usize>(
+        meta: &mut ConstraintSystem<F>,
+    ) -> LtConfig<F, N> {
+        let columns = [(); 6].map(|_| meta.advice_column());
+        let bytes = [(); N].map(|_| meta.advice_column());
+        let table = RangeTableConfig::configure(meta);
+        LtChip::configure(meta, columns, bytes, table)
+    }
+
+    // instance: [a < b, a <= b, a > b, a >= b, min(a, b), max(a, b), clamp(c, a, b)]
+    #[derive(Default)]
+    struct CompareCircuit<F, const N: usize> {
+        a: Value<F>,
+        b: Value<F>,
+        c: Value<F>,
+    }
+
+    impl<F: PrimeField, const N: usize> CompareCircuit<F, N> {
+        fn new(a: u64, b: u64, c: u64) -> Self {
+            Self {
+                a: Value::known(F::from(a)),
+                b: Value::known(F::from(b)),
+                c: Value::known(F::from(c)),
+            }
+        }
+    }
+
+    impl<F: PrimeField, const N: usize> Circuit<F> for CompareCircuit<F, N> {
+        type Config = (LtConfig<F, N>, Column<Advice>, Column<Instance>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let input = meta.advice_column();
+            let instance = meta.instance_column();
+            meta.enable_equality(input);
+            meta.enable_equality(instance);
+            (configure_lt(meta), input, instance)
+        }
+
+        fn synthesize(
+            &self,
+            (config, input, instance): Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            config.table.load(&mut layouter)?;
+            let chip = LtChip::construct(config);
+
+            let (a, b, c) = layouter.assign_region(
+                || "inputs",
+                |mut region| {
+                    let a = region.assign_advice(|| "a", input, 0, || self.a)?;
+                    let b = region.assign_advice(|| "b", input, 1, || self.b)?;
+                    let c = region.assign_advice(|| "c", input, 2, || self.c)?;
+                    Ok((a, b, c))
+                },
+            )?;
+
+            let outputs = [
+                chip.lt(layouter.namespace(|| "lt"), &a, &b)?,
+                chip.le(layouter.namespace(|| "le"), &a, &b)?,
+                chip.gt(layouter.namespace(|| "gt"), &a, &b)?,
+                chip.ge(layouter.namespace(|| "ge"), &a, &b)?,
+                chip.min(layouter.namespace(|| "min"), &a, &b)?,
+                chip.max(layouter.namespace(|| "max"), &a, &b)?,
+                chip.clamp(layouter.namespace(|| "clamp"), &c, &a, &b)?,
+            ];
+            for (row, cell) in outputs.iter().enumerate() {
+                layouter.constrain_instance(cell.cell(), instance, row)?;
+            }
+            Ok(())
+        }
+    }
+
+    fn expected(a: u64, b: u64, c: u64) -> Vec<Fp> {
+        let bit = |b: bool| Fp::from(b as u64);
+        vec![
+            bit(a < b),
+            bit(a <= b),
+            bit(a > b),
+            bit(a >= b),
+            Fp::from(a.min(b)),
+            Fp::from(a.max(b)),
+            Fp::from(c.max(a).min(b)),
+        ]
+    }
+
+    fn check<const N: usize>(values: &[u64], c: u64) {
+        for &a in values {
+            for &b in values {
+                let circuit = CompareCircuit::<Fp, N>::new(a, b, c);
+                assert_instance_bound(&circuit, &expected(a, b, c));
+            }
+        }
+    }
+
+    #[test]
+    fn test_lt_one_byte() {
+        check::<1>(&[0, 1, 127, 128, 254, 255], 128);
+    }
+
+    #[test]
+    fn test_lt_two_bytes() {
+        check::<2>(&[0, 255, 256, 65534, 65535], 300);
+    }
+
+    // writes one comparator row with a chosen `lt`, bypassing `compare`
+    struct ForgedCircuit {
+        a: u64,
+        b: u64,
+        lt: bool,
+    }
+
+    impl Circuit<Fp> for ForgedCircuit {
+        type Config = LtConfig<Fp, 1>;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self { ..*self }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            configure_lt(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            config.table.load(&mut layouter)?;
+
+            let (a, b) = (Fp::from(self.a), Fp::from(self.b));
+            let lt = Fp::from(self.lt as u64);
+            let diff = a - b + lt * Fp::from(256);
+            let min = b + lt * (a - b);
+
+            layouter.assign_region(
+                || "forged",
+                |mut region| {
+                    config.q_lt.enable(&mut region, 0)?;
+                    for (name, column, value) in [
+                        ("a", config.a, a),
+                        ("b", config.b, b),
+                        ("lt", config.lt, lt),
+                        ("ge", config.ge, Fp::one() - lt),
+                        ("min", config.min, min),
+                        ("max", config.max, a + b - min),
+                        // the best a cheating prover can do: the low byte of diff
+                        ("byte", config.bytes[0], Fp::from(le_bytes(diff)[0] as u64)),
+                    ] {
+                        region.assign_advice(|| name, column, 0, || Value::known(value))?;
+                    }
+                    Ok(())
+                },
+            )
+        }
+    }
+
+    #[test]
+    fn test_lt_forged() {
+        let k = 9;
+        for (a, b) in [
+            (0, 0),
+            (0, 255),
+            (255, 0),
+            (255, 255),
+            (127, 128),
+            (128, 127),
+        ] {
+            for lt in [false, true] {
+                let prover = MockProver::run(k, &ForgedCircuit { a, b, lt }, vec![]).unwrap();
+                assert_eq!(
+                    prover.verify().is_ok(),
+                    lt == (a < b),
+                    "({a}, {b}) with lt = {lt}"
+                );
+            }
+        }
+
+        // 2^8 is out of range for one byte, no answer can be proven against 0
+        for lt in [false, true] {
+            let prover = MockProver::run(k, &ForgedCircuit { a: 256, b: 0, lt }, vec![]).unwrap();
+            assert!(prover.verify().is_err(), "256 vs 0 with lt = {lt}");
+        }
+    }
+}
@@ -0,0 +1,522 @@
+use std::{fmt, marker::PhantomData};
+
+use ff::{Field, PrimeField};
+use halo2_proofs::{circuit::*, plonk::*, poly::Rotation};
+
+use crate::is_zero::{IsZeroChip, IsZeroConfig};
+
+// q = a / b over the field, in two modes sharing one row layout:
+//
+//   a | b | quotient | b_inv | b_is_zero | q_div | q_safe_div
+//
+// strict (q_div), b != 0 is part of the statement:
+//   b · b_inv    = 1
+//   b · quotient = a
+//
+// safe (q_safe_div), b = 0 is allowed and flagged:
+//   b_is_zero             = is_zero(b)             (IsZeroChip, output mode)
+//   b · quotient          = a · (1 - b_is_zero)
+//   b_is_zero · quotient  = 0                      so a / 0 is 0
+//
+// An honest prover asked for a strict division by zero gets `DivError::DivisionByZero`
+// instead of an unsatisfiable circuit.
+
+#[derive(Debug)]
+pub enum DivError {
+    /// Strict division with a known zero divisor.
+    DivisionByZero,
+    /// Any other failure while laying out the division.
+    Synthesis(Error),
+}
+
+impl fmt::Display for DivError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DivError::DivisionByZero => write!(f, "division by zero"),
+            DivError::Synthesis(e) => write!(f, "cannot lay out the division: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for DivError {}
+
+impl From<Error> for DivError {
+    fn from(e: Error) -> Self {
+        DivError::Synthesis(e)
+    }
+}
+
+// so `?` works inside `Circuit::synthesize`
+impl From<DivError> for Error {
+    fn from(e: DivError) -> Self {
+        match e {
+            DivError::DivisionByZero => Error::Synthesis,
+            DivError::Synthesis(e) => e,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DivConfig<F: PrimeField> {
+    a: Column<Advice>,
+    b: Column<Advice>,
+    quotient: Column<Advice>,
+    b_inv: Column<Advice>,
+    q_div: Selector,
+    q_safe_div: Selector,
+    b_is_zero: IsZeroConfig<F>,
+}
+
+pub struct DivChip<F: PrimeField> {
+    config: DivConfig<F>,
+}
+
+impl<F: PrimeField> DivChip<F> {
+    pub fn construct(config: DivConfig<F>) -> Self {
+        Self { config }
+    }
+
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        [a, b, quotient, b_inv, b_is_zero]: [Column<Advice>; 5],
+    ) -> DivConfig<F> {
+        let q_div = meta.selector();
+        let q_safe_div = meta.selector();
+        for column in [a, b, quotient] {
+            meta.enable_equality(column);
+        }
+
+        meta.create_gate("div", |meta| {
+            let q = meta.query_selector(q_div);
+            let a = meta.query_advice(a, Rotation::cur());
+            let b = meta.query_advice(b, Rotation::cur());
+            let quotient = meta.query_advice(quotient, Rotation::cur());
+            let b_inv = meta.query_advice(b_inv, Rotation::cur());
+
+            Constraints::with_selector(
+                q,
+                [
+                    ("b != 0", b.clone() * b_inv - Expression::Constant(F::ONE)),
+                    ("b · quotient = a", b * quotient - a),
+                ],
+            )
+        });
+
+        // the inverse column doubles as the is-zero witness of b
+        let b_is_zero = IsZeroChip::configure_with_output(
+            meta,
+            |meta| meta.query_selector(q_safe_div),
+            |meta| meta.query_advice(b, Rotation::cur()),
+            b_inv,
+            b_is_zero,
+        );
+
+        meta.create_gate("safe div", |meta| {
+            let q = meta.query_selector(q_safe_div);
+            let a = meta.query_advice(a, Rotation::cur());
+            let b = meta.query_advice(b, Rotation::cur());
+            let quotient = meta.query_advice(quotient, Rotation::cur());
+            let b_is_zero = b_is_zero.expr();
+
+            Constraints::with_selector(
+                q,
+                [
+                    (
+                        "b · quotient = a",
+                        b * quotient.clone()
+                            - a * (Expression::Constant(F::ONE) - b_is_zero.clone()),
+                    ),
+                    ("a / 0 = 0", b_is_zero * quotient),
+                ],
+            )
+        });
+
+        DivConfig {
+            a,
+            b,
+            quotient,
+            b_inv,
+            q_div,
+            q_safe_div,
+            b_is_zero,
+        }
+    }
+
+    /// `a / b`, with `b != 0` enforced by the circuit.
+    pub fn div(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &AssignedCell<F, F>,
+        b: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, DivError> {
+        if b.value().error_if_known_and(|b| **b == F::ZERO).is_err() {
+            return Err(DivError::DivisionByZero);
+        }
+
+        let config = &self.config;
+        let quotient = layouter.assign_region(
+            || "div",
+            |mut region| {
+                config.q_div.enable(&mut region, 0)?;
+                let (a, b) = self.copy_operands(&mut region, a, b)?;
+
+                let b_inv = b.map(|b| b.invert().unwrap());
+                region.assign_advice(|| "b inv", config.b_inv, 0, || b_inv)?;
+                region.assign_advice(|| "quotient", config.quotient, 0, || a * b_inv)
+            },
+        )?;
+        Ok(quotient)
+    }
+
+    /// `a / b` and whether `b = 0`, in which case the quotient is 0.
+    pub fn safe_div(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: &AssignedCell<F, F>,
+        b: &AssignedCell<F, F>,
+    ) -> Result<(AssignedCell<F, F>, AssignedCell<F, F>), Error> {
+        let config = &self.config;
+        let is_zero_chip = IsZeroChip::construct(config.b_is_zero.clone());
+
+        layouter.assign_region(
+            || "safe div",
+            |mut region| {
+                config.q_safe_div.enable(&mut region, 0)?;
+                let (a, b) = self.copy_operands(&mut region, a, b)?;
+
+                let b_is_zero = is_zero_chip.assign(&mut region, 0, b)?;
+                let b_inv = b.map(|b| b.invert().unwrap_or(F::ZERO));
+                let quotient =
+                    region.assign_advice(|| "quotient", config.quotient, 0, || a * b_inv)?;
+                Ok((
+                    quotient,
+                    b_is_zero.expect("configured with an output column"),
+                ))
+            },
+        )
+    }
+
+    fn copy_operands(
+        &self,
+        region: &mut Region<'_, F>,
+        a: &AssignedCell<F, F>,
+        b: &AssignedCell<F, F>,
+    ) -> Result<(Value<F>, Value<F>), Error> {
+        let a = a.copy_advice(|| "a", region, self.config.a, 0)?;
+        let b = b.copy_advice(|| "b", region, self.config.b, 0)?;
+        Ok((a.value().copied(), b.value().copied()))
+    }
+}
+
+// x^-1 together with a flag, 0 and 0 for x = 0:
+//
+//   x | x_inv | invertible | q_invert
+//
+//   invertible               = x · x_inv
+//   x · (1 - invertible)     = 0               x != 0 forces x_inv = 1 / x
+//   x_inv · (1 - invertible) = 0               x = 0 forces x_inv = 0
+
+#[derive(Debug, Clone)]
+pub struct InvertConfig {
+    x: Column<Advice>,
+    x_inv: Column<Advice>,
+    invertible: Column<Advice>,
+    q_invert: Selector,
+}
+
+pub struct InvertChip<F: PrimeField> {
+    config: InvertConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField> InvertChip<F> {
+    pub fn construct(config: InvertConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        [x, x_inv, invertible]: [Column<Advice>; 3],
+    ) -> InvertConfig {
+        let q_invert = meta.selector();
+        for column in [x, x_inv, invertible] {
+            meta.enable_equality(column);
+        }
+
+        meta.create_gate("invert", |meta| {
+            let q = meta.query_selector(q_invert);
+            let x = meta.query_advice(x, Rotation::cur());
+            let x_inv = meta.query_advice(x_inv, Rotation::cur());
+            let invertible = meta.query_advice(invertible, Rotation::cur());
+            let not_invertible = Expression::Constant(F::ONE) - invertible.clone();
+
+            Constraints::with_selector(
+                q,
+                [
+                    ("invertible", invertible - x.clone() * x_inv.clone()),
+                    ("x != 0", x * not_invertible.clone()),
+                    ("0^-1 = 0", x_inv * not_invertible),
+                ],
+            )
+        });
+
+        InvertConfig {
+            x,
+            x_inv,
+            invertible,
+            q_invert,
+        }
+    }
+
+    /// Returns the inverse of `x` (0 for 0) and a flag that is 1 iff `x` is invertible.
+    pub fn invert(
+        &self,
+        mut layouter: impl Layouter<F>,
+        x: &AssignedCell<F, F>,
+    ) -> Result<(AssignedCell<F, F>, AssignedCell<F, F>), Error> {
+        let config = &self.config;
+
+        layouter.assign_region(
+            || "invert",
+            |mut region| {
+                config.q_invert.enable(&mut region, 0)?;
+                let x = x.copy_advice(|| "x", &mut region, config.x, 0)?;
+
+                let x_inv = x.value().map(|x| x.invert().unwrap_or(F::ZERO));
+                let invertible = x.value().copied() * x_inv;
+                let x_inv = region.assign_advice(|| "x inv", config.x_inv, 0, || x_inv)?;
+                let invertible =
+                    region.assign_advice(|| "invertible", config.invertible, 0, || invertible)?;
+                Ok((x_inv, invertible))
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        proof::setup,
+        rows::{assert_instance_bound, min_k},
+    };
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    // instance: [safe a / b, b == 0, b^-1, b invertible] and, if `strict`, [a / b]
+    #[derive(Default)]
+    struct DivCircuit<F> {
+        a: Value<F>,
+        b: Value<F>,
+        strict: bool,
+    }
+
+    impl<F: PrimeField> Circuit<F> for DivCircuit<F> {
+        type Config = (Column<Advice>, DivConfig<F>, InvertConfig, Column<Instance>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                strict: self.strict,
+                ..Self::default()
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let input = meta.advice_column();
+            let instance = meta.instance_column();
+            meta.enable_equality(input);
+            meta.enable_equality(instance);
+
+            let div_columns = [(); 5].map(|_| meta.advice_column());
+            let invert_columns = [(); 3].map(|_| meta.advice_column());
+            let div = DivChip::configure(meta, div_columns);
+            let invert = InvertChip::configure(meta, invert_columns);
+            (input, div, invert, instance)
+        }
+
+        fn synthesize(
+            &self,
+            (input, div, invert, instance): Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let div_chip = DivChip::construct(div);
+            let invert_chip = InvertChip::construct(invert);
+
+            let (a, b) = layouter.assign_region(
+                || "inputs",
+                |mut region| {
+                    let a = region.assign_advice(|| "a", input, 0, || self.a)?;
+                    let b = region.assign_advice(|| "b", input, 1, || self.b)?;
+                    Ok((a, b))
+                },
+            )?;
+
+            let (quotient, b_is_zero) = div_chip.safe_div(layouter.namespace(|| "safe"), &a, &b)?;
+            let (b_inv, invertible) = invert_chip.invert(layouter.namespace(|| "invert"), &b)?;
+            let mut outputs = vec![quotient, b_is_zero, b_inv, invertible];
+
+            if self.strict {
+                let quotient = div_chip.div(layouter.namespace(|| "strict"), &a, &b);
+                if self.b.error_if_known_and(|b| *b == F::ZERO).is_err() {
+                    assert!(matches!(quotient, Err(DivError::DivisionByZero)));
+                }
+                outputs.push(quotient?);
+            }
+
+            for (row, cell) in outputs.iter().enumerate() {
+                layouter.constrain_instance(cell.cell(), instance, row)?;
+            }
+            Ok(())
+        }
+    }
+
+    fn circuit(a: u64, b: u64, strict: bool) -> DivCircuit<Fp> {
+        DivCircuit {
+            a: Value::known(Fp::from(a)),
+            b: Value::known(Fp::from(b)),
+            strict,
+        }
+    }
+
+    #[test]
+    fn test_div() {
+        let k = min_k(&DivCircuit::<Fp> {
+            strict: true,
+            ..Default::default()
+        })
+        .unwrap();
+
+        for (a, b) in [(12, 4), (7, 2), (0, 5), (1, 1), (5, 0), (0, 0)] {
+            let (a_f, b_f) = (Fp::from(a), Fp::from(b));
+            let b_inv = b_f.invert().unwrap_or(Fp::zero());
+            let mut instance = vec![
+                a_f * b_inv,
+                Fp::from((b == 0) as u64),
+                b_inv,
+                Fp::from((b != 0) as u64),
+            ];
+            assert_instance_bound(&circuit(a, b, false), &instance);
+
+            instance.push(a_f * b_inv);
+            let strict = MockProver::run(k, &circuit(a, b, true), vec![instance]);
+            if b == 0 {
+                // the honest prover refuses to divide by zero
+                assert!(matches!(strict, Err(Error::Synthesis)), "{a} / 0");
+            } else {
+                strict.unwrap().assert_satisfied();
+            }
+        }
+    }
+
+    #[test]
+    fn test_div_keygen_without_witnesses() {
+        let circuit = DivCircuit::<Fp> {
+            strict: true,
+            ..Default::default()
+        };
+        // unknown divisors are not rejected
+        setup(min_k(&circuit).unwrap(), &circuit).unwrap();
+    }
+
+    // writes a division row directly, as a cheating prover could
+    struct ForgedCircuit {
+        b: Fp,
+        quotient: Fp,
+        b_inv: Fp,
+        b_is_zero: Fp,
+        strict: bool,
+    }
+
+    impl Circuit<Fp> for ForgedCircuit {
+        type Config = DivConfig<Fp>;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self { ..*self }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let columns = [(); 5].map(|_| meta.advice_column());
+            DivChip::configure(meta, columns)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            layouter.assign_region(
+                || "forged",
+                |mut region| {
+                    if self.strict {
+                        config.q_div.enable(&mut region, 0)?;
+                    } else {
+                        config.q_safe_div.enable(&mut region, 0)?;
+                        let output = config.b_is_zero.output.unwrap();
+                        region.assign_advice(
+                            || "b is zero",
+                            output,
+                            0,
+                            || Value::known(self.b_is_zero),
+                        )?;
+                    }
+                    for (column, value) in [
+                        (config.a, Fp::from(5)),
+                        (config.b, self.b),
+                        (config.quotient, self.quotient),
+                        (config.b_inv, self.b_inv),
+                    ] {
+                        region.assign_advice(|| "forged", column, 0, || Value::known(value))?;
+                    }
+                    Ok(())
+                },
+            )
+        }
+    }
+
+    #[test]
+    fn test_div_by_zero_forged() {
+        let k = 4;
+        let zero = Fp::zero();
+        for forged in [
+            // strict: no b_inv makes 0 · b_inv = 1
+            ForgedCircuit {
+                b: zero,
+                quotient: Fp::from(3),
+                b_inv: Fp::from(7),
+                b_is_zero: zero,
+                strict: true,
+            },
+            // safe: claiming b != 0 for b = 0
+            ForgedCircuit {
+                b: zero,
+                quotient: zero,
+                b_inv: zero,
+                b_is_zero: zero,
+                strict: false,
+            },
+            // safe: a non-zero quotient for b = 0
+            ForgedCircuit {
+                b: zero,
+                quotient: Fp::from(3),
+                b_inv: zero,
+                b_is_zero: Fp::one(),
+                strict: false,
+            },
+            // safe: claiming b = 0 for b = 2
+            ForgedCircuit {
+                b: Fp::from(2),
+                quotient: zero,
+                b_inv: zero,
+                b_is_zero: Fp::one(),
+                strict: false,
+            },
+        ] {
+            let prover = MockProver::run(k, &forged, vec![]).unwrap();
+            assert!(prover.verify().is_err(), "b = {:?}", forged.b);
+        }
+    }
+}
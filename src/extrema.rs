@@ -0,0 +1,339 @@
+use ff::PrimeField;
+use halo2_proofs::{circuit::*, plonk::*};
+
+use crate::{
+    comparator::{LtChip, LtConfig},
+    select::{MuxChip, MuxConfig, Muxed},
+};
+
+// max, min, argmax and argmin of N values in [0, 2^(8·N_BYTES)).
+//
+// The extremes are running comparisons, one comparator row per step:
+//
+//   max_0 = x_0,  max_i = max(max_{i-1}, x_i)
+//   min_0 = x_0,  min_i = min(min_{i-1}, x_i)
+//
+// The index is witnessed and fed to a mux, whose gate binds it to a one-hot selector
+// (index = Σ i · s_i), and the muxed input is constrained equal to the extreme:
+//
+//   x_argmax = max_{N-1}
+//
+// So the index provably points at a maximum. On ties any such index is accepted,
+// the witness takes the first one.
+//
+// Like LtChip, the inputs must already be range-checked to N_BYTES bytes.
+
+#[derive(Debug, Clone)]
+pub struct ExtremaConfig<F: PrimeField, const N_BYTES: usize, const N: usize> {
+    lt: LtConfig<F, N_BYTES>,
+    mux: MuxConfig<N>,
+    index: Column<Advice>,
+}
+
+/// The extremes of N cells, each with the mux row that locates it.
+#[derive(Debug, Clone)]
+pub struct Extrema<F: PrimeField, const N: usize> {
+    pub max: AssignedCell<F, F>,
+    pub min: AssignedCell<F, F>,
+    pub argmax: Muxed<F, N>,
+    pub argmin: Muxed<F, N>,
+}
+
+pub struct ExtremaChip<F: PrimeField, const N_BYTES: usize, const N: usize> {
+    config: ExtremaConfig<F, N_BYTES, N>,
+}
+
+impl<F: PrimeField, const N_BYTES: usize, const N: usize> ExtremaChip<F, N_BYTES, N> {
+    pub fn construct(config: ExtremaConfig<F, N_BYTES, N>) -> Self {
+        Self { config }
+    }
+
+    /// `index` holds the witnessed argmax / argmin before they are copied into the mux.
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        lt: LtConfig<F, N_BYTES>,
+        mux: MuxConfig<N>,
+        index: Column<Advice>,
+    ) -> ExtremaConfig<F, N_BYTES, N> {
+        meta.enable_equality(index);
+        ExtremaConfig { lt, mux, index }
+    }
+
+    fn lt_chip(&self) -> LtChip<F, N_BYTES> {
+        LtChip::construct(self.config.lt.clone())
+    }
+
+    pub fn max(
+        &self,
+        mut layouter: impl Layouter<F>,
+        inputs: &[AssignedCell<F, F>; N],
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let chip = self.lt_chip();
+        let mut max = inputs[0].clone();
+        for (i, x) in inputs.iter().enumerate().skip(1) {
+            max = chip.max(layouter.namespace(|| format!("max {i}")), &max, x)?;
+        }
+        Ok(max)
+    }
+
+    pub fn min(
+        &self,
+        mut layouter: impl Layouter<F>,
+        inputs: &[AssignedCell<F, F>; N],
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let chip = self.lt_chip();
+        let mut min = inputs[0].clone();
+        for (i, x) in inputs.iter().enumerate().skip(1) {
+            min = chip.min(layouter.namespace(|| format!("min {i}")), &min, x)?;
+        }
+        Ok(min)
+    }
+
+    /// Proves `inputs[index] == target` for a witnessed `index` in `0..N`.
+    pub fn position(
+        &self,
+        mut layouter: impl Layouter<F>,
+        inputs: &[AssignedCell<F, F>; N],
+        target: &AssignedCell<F, F>,
+        index: Value<F>,
+    ) -> Result<Muxed<F, N>, Error> {
+        let index = layouter.assign_region(
+            || "index",
+            |mut region| region.assign_advice(|| "index", self.config.index, 0, || index),
+        )?;
+        let muxed = MuxChip::construct(self.config.mux.clone()).mux_index(
+            layouter.namespace(|| "mux"),
+            inputs,
+            &index,
+        )?;
+        layouter.assign_region(
+            || "muxed == target",
+            |mut region| region.constrain_equal(muxed.out.cell(), target.cell()),
+        )?;
+        Ok(muxed)
+    }
+
+    pub fn extrema(
+        &self,
+        mut layouter: impl Layouter<F>,
+        inputs: &[AssignedCell<F, F>; N],
+    ) -> Result<Extrema<F, N>, Error> {
+        let max = self.max(layouter.namespace(|| "max"), inputs)?;
+        let min = self.min(layouter.namespace(|| "min"), inputs)?;
+
+        let argmax = first_index(inputs, &max);
+        let argmax = self.position(layouter.namespace(|| "argmax"), inputs, &max, argmax)?;
+        let argmin = first_index(inputs, &min);
+        let argmin = self.position(layouter.namespace(|| "argmin"), inputs, &min, argmin)?;
+
+        Ok(Extrema {
+            max,
+            min,
+            argmax,
+            argmin,
+        })
+    }
+}
+
+// the first i with inputs[i] == target, or N (which the mux rejects) if there is none
+fn first_index<F: PrimeField, const N: usize>(
+    inputs: &[AssignedCell<F, F>; N],
+    target: &AssignedCell<F, F>,
+) -> Value<F> {
+    let values = inputs.iter().fold(Value::known(vec![]), |acc, x| {
+        acc.zip(x.value()).map(|(mut values, x)| {
+            values.push(*x);
+            values
+        })
+    });
+    values.zip(target.value()).map(|(values, target)| {
+        let index = values.iter().position(|x| x == target).unwrap_or(N);
+        F::from(index as u64)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        range_check::example2::table::RangeTableConfig,
+        rows::{assert_instance_bound, min_k, mock_verify},
+    };
+    use halo2_proofs::{dev::MockProver, pasta::Fp, poly::Rotation};
+
+    const N: usize = 4;
+
+    #[derive(Debug, Clone)]
+    struct TestConfig {
+        extrema: ExtremaConfig<Fp, 1, N>,
+        table: RangeTableConfig<Fp, 256>,
+        q_input: Selector,
+        input: Column<Advice>,
+        instance: Column<Instance>,
+    }
+
+    // instance: [max, argmax, min, argmin]
+    //
+    // `argmax` overrides the witnessed argmax, to check that a wrong index is rejected
+    #[derive(Default)]
+    struct ExtremaCircuit {
+        inputs: [Value<Fp>; N],
+        argmax: Option<u64>,
+    }
+
+    impl ExtremaCircuit {
+        fn new(inputs: [u64; N]) -> Self {
+            Self {
+                inputs: inputs.map(|x| Value::known(Fp::from(x))),
+                argmax: None,
+            }
+        }
+    }
+
+    impl Circuit<Fp> for ExtremaCircuit {
+        type Config = TestConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                argmax: self.argmax,
+                ..Self::default()
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let table = RangeTableConfig::configure(meta);
+            let lt_columns = [(); 6].map(|_| meta.advice_column());
+            let byte = meta.advice_column();
+            let lt = LtChip::configure(meta, lt_columns, [byte], table.clone());
+            let mux_inputs = [(); N].map(|_| meta.advice_column());
+            let one_hot = [(); N].map(|_| meta.advice_column());
+            let mux_outputs = [(); 2].map(|_| meta.advice_column());
+            let mux = MuxChip::configure(meta, mux_inputs, one_hot, mux_outputs);
+            let index = meta.advice_column();
+            let extrema = ExtremaChip::configure(meta, lt, mux, index);
+
+            // the inputs are range-checked by the comparator's byte table
+            let q_input = meta.complex_selector();
+            let input = meta.advice_column();
+            meta.enable_equality(input);
+            meta.lookup(|meta| {
+                let q = meta.query_selector(q_input);
+                let input = meta.query_advice(input, Rotation::cur());
+                vec![(q * input, table.value)]
+            });
+
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+
+            TestConfig {
+                extrema,
+                table,
+                q_input,
+                input,
+                instance,
+            }
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            config.table.load(&mut layouter)?;
+            let chip = ExtremaChip::construct(config.extrema);
+
+            let inputs: [AssignedCell<Fp, Fp>; N] = layouter.assign_region(
+                || "inputs",
+                |mut region| {
+                    let mut inputs = vec![];
+                    for (row, &x) in self.inputs.iter().enumerate() {
+                        config.q_input.enable(&mut region, row)?;
+                        inputs.push(region.assign_advice(|| "x", config.input, row, || x)?);
+                    }
+                    Ok(inputs.try_into().unwrap())
+                },
+            )?;
+
+            let (max, argmax, min, argmin) = match self.argmax {
+                None => {
+                    let Extrema {
+                        max,
+                        min,
+                        argmax,
+                        argmin,
+                    } = chip.extrema(layouter.namespace(|| "extrema"), &inputs)?;
+                    (max, argmax.index, min, argmin.index)
+                }
+                Some(index) => {
+                    let max = chip.max(layouter.namespace(|| "max"), &inputs)?;
+                    let min = chip.min(layouter.namespace(|| "min"), &inputs)?;
+                    let argmax = chip.position(
+                        layouter.namespace(|| "forged argmax"),
+                        &inputs,
+                        &max,
+                        Value::known(Fp::from(index)),
+                    )?;
+                    let argmin = chip.position(
+                        layouter.namespace(|| "argmin"),
+                        &inputs,
+                        &min,
+                        first_index(&inputs, &min),
+                    )?;
+                    (max, argmax.index, min, argmin.index)
+                }
+            };
+
+            for (row, cell) in [max, argmax, min, argmin].iter().enumerate() {
+                layouter.constrain_instance(cell.cell(), config.instance, row)?;
+            }
+            Ok(())
+        }
+    }
+
+    fn expected(inputs: [u64; N]) -> Vec<Fp> {
+        let max = *inputs.iter().max().unwrap();
+        let min = *inputs.iter().min().unwrap();
+        let argmax = inputs.iter().position(|&x| x == max).unwrap();
+        let argmin = inputs.iter().position(|&x| x == min).unwrap();
+        [max, argmax as u64, min, argmin as u64]
+            .map(Fp::from)
+            .to_vec()
+    }
+
+    #[test]
+    fn test_extrema() {
+        for inputs in [
+            [3, 9, 1, 4],
+            [9, 3, 9, 1],
+            [7, 7, 7, 7],
+            [0, 255, 128, 0],
+            [255, 254, 1, 0],
+            [1, 2, 3, 4],
+        ] {
+            assert_instance_bound(&ExtremaCircuit::new(inputs), &expected(inputs));
+        }
+
+        // inputs outside the byte table are rejected
+        let circuit = ExtremaCircuit::new([3, 256, 1, 4]);
+        assert!(!mock_verify(&circuit, vec![expected([3, 256, 1, 4])]));
+    }
+
+    #[test]
+    fn test_extrema_forged_argmax() {
+        let k = min_k(&ExtremaCircuit::default()).unwrap();
+        let inputs = [3, 9, 1, 9];
+        for index in 0..=N as u64 {
+            let circuit = ExtremaCircuit {
+                argmax: Some(index),
+                ..ExtremaCircuit::new(inputs)
+            };
+            let mut instance = expected(inputs);
+            instance[1] = Fp::from(index);
+            let prover = MockProver::run(k, &circuit, vec![instance]).unwrap();
+
+            // both maxima are fine, any other index (or N) is not
+            assert_eq!(prover.verify().is_ok(), index == 1 || index == 3, "{index}");
+        }
+    }
+}
@@ -1,3 +1,7 @@
-mod example1;
-mod example2;
-mod example3;
+pub mod batch;
+pub mod example1;
+pub mod example2;
+pub mod example3;
+pub mod fast_doubling;
+pub mod linear_recurrence;
+pub mod private_index;
@@ -0,0 +1,233 @@
+use std::marker::PhantomData;
+
+use ff::{Field, PrimeField};
+use halo2_proofs::{circuit::*, plonk::*};
+
+use super::{
+    example1::nth_term,
+    example2::{FiboChip, FiboConfig},
+};
+
+// M independent example2 sequences in one proof. Sequence j reads its seeds from
+// instance rows 3j and 3j + 1 and exposes f(n_j) at row 3j + 2:
+//
+//   instance = [a_0, b_0, out_0, a_1, b_1, out_1, ..., a_{M-1}, b_{M-1}, out_{M-1}]
+//
+// Two layouts of the same sequences:
+//
+//   Parallel: one advice column (and selector) per sequence, all tables start at
+//             row 0, so max(n_j) + 1 rows and M advice columns.
+//   Stacked:  a single advice column, the tables are placed one below the other,
+//             so Σ (n_j + 1) rows and one advice column.
+//
+// `rows::layout_report` measures both.
+
+/// Lays out the public instance for `(a, b, n)` triples.
+pub fn batch_instance<F: Field>(sequences: &[(F, F, usize)]) -> Vec<F> {
+    sequences
+        .iter()
+        .flat_map(|&(a, b, n)| [a, b, nth_term(a, b, n)])
+        .collect()
+}
+
+fn assign_batch<F: PrimeField>(
+    mut layouter: impl Layouter<F>,
+    ns: &[usize],
+    chip: impl Fn(usize) -> FiboChip<F>,
+) -> Result<(), Error> {
+    // like example2, every table needs at least n = 3
+    if ns.iter().any(|&n| n < 3) {
+        return Err(Error::Synthesis);
+    }
+    for (j, &n) in ns.iter().enumerate() {
+        let chip = chip(j);
+        let out =
+            chip.assign_seeded(layouter.namespace(|| format!("sequence {j}")), 3 * j, n + 1)?;
+        chip.expose_public(layouter.namespace(|| format!("out {j}")), out, 3 * j + 2)?;
+    }
+    Ok(())
+}
+
+/// `M` sequences side by side, one advice column each.
+#[derive(Debug, Clone)]
+pub struct ParallelBatchCircuit<F, const M: usize> {
+    pub ns: [usize; M],
+    _marker: PhantomData<F>,
+}
+
+impl<F, const M: usize> ParallelBatchCircuit<F, M> {
+    pub fn new(ns: [usize; M]) -> Self {
+        Self {
+            ns,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<F: PrimeField, const M: usize> Circuit<F> for ParallelBatchCircuit<F, M> {
+    type Config = [FiboConfig; M];
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::new(self.ns)
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let instance = meta.instance_column();
+        [(); M].map(|_| {
+            let advice = meta.advice_column();
+            FiboChip::configure(meta, advice, instance)
+        })
+    }
+
+    fn synthesize(&self, config: Self::Config, layouter: impl Layouter<F>) -> Result<(), Error> {
+        assign_batch(layouter, &self.ns, |j| {
+            FiboChip::construct(config[j].clone())
+        })
+    }
+}
+
+/// `M` sequences one below the other in a single advice column.
+#[derive(Debug, Clone)]
+pub struct StackedBatchCircuit<F, const M: usize> {
+    pub ns: [usize; M],
+    _marker: PhantomData<F>,
+}
+
+impl<F, const M: usize> StackedBatchCircuit<F, M> {
+    pub fn new(ns: [usize; M]) -> Self {
+        Self {
+            ns,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<F: PrimeField, const M: usize> Circuit<F> for StackedBatchCircuit<F, M> {
+    type Config = FiboConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::new(self.ns)
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let advice = meta.advice_column();
+        let instance = meta.instance_column();
+        FiboChip::configure(meta, advice, instance)
+    }
+
+    fn synthesize(&self, config: Self::Config, layouter: impl Layouter<F>) -> Result<(), Error> {
+        assign_batch(layouter, &self.ns, |_| FiboChip::construct(config.clone()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        proof::prove_and_verify,
+        rows::{assert_instance_bound, layout_report, min_k, mock_verify},
+    };
+    use halo2_proofs::pasta::Fp;
+
+    fn sequences() -> [(Fp, Fp, usize); 4] {
+        [
+            (Fp::from(1), Fp::from(1), 9),
+            (Fp::from(0), Fp::from(1), 20),
+            (Fp::from(2), Fp::from(1), 5),
+            (Fp::from(5), Fp::from(7), 12),
+        ]
+    }
+
+    fn check<C: Circuit<Fp>>(circuit: &C) {
+        // every seed and output is checked against its own sequence
+        let instance = batch_instance(&sequences());
+        assert_instance_bound(circuit, &instance);
+
+        // outputs of different sequences cannot be swapped
+        let mut swapped = instance;
+        swapped.swap(2, 5);
+        assert!(!mock_verify(circuit, vec![swapped]));
+    }
+
+    #[test]
+    fn test_batch_parallel() {
+        check(&ParallelBatchCircuit::<Fp, 4>::new([9, 20, 5, 12]));
+    }
+
+    #[test]
+    fn test_batch_stacked() {
+        check(&StackedBatchCircuit::<Fp, 4>::new([9, 20, 5, 12]));
+    }
+
+    #[test]
+    fn test_batch_layout_report() {
+        let parallel = layout_report(&ParallelBatchCircuit::<Fp, 4>::new([9, 20, 5, 12])).unwrap();
+        let stacked = layout_report(&StackedBatchCircuit::<Fp, 4>::new([9, 20, 5, 12])).unwrap();
+
+        // the instance column holds 12 rows, the longest table 21
+        assert_eq!(
+            (parallel.rows, parallel.k, parallel.advice_columns),
+            (21, 5, 4)
+        );
+        assert_eq!(parallel.selectors, 4);
+        // 10 + 21 + 6 + 13 rows
+        assert_eq!(
+            (stacked.rows, stacked.k, stacked.advice_columns),
+            (50, 6, 1)
+        );
+        assert_eq!(stacked.selectors, 1);
+        assert_eq!(
+            (parallel.instance_columns, stacked.instance_columns),
+            (1, 1)
+        );
+
+        assert!(matches!(
+            layout_report(&StackedBatchCircuit::<Fp, 2>::new([9, 2])),
+            Err(crate::rows::KError::Synthesis(Error::Synthesis))
+        ));
+    }
+
+    #[test]
+    fn test_batch_real_prover() {
+        let instance = batch_instance(&sequences());
+
+        let circuit = ParallelBatchCircuit::<Fp, 4>::new([9, 20, 5, 12]);
+        prove_and_verify(min_k(&circuit).unwrap(), circuit, &[instance.clone()]).unwrap();
+
+        let circuit = StackedBatchCircuit::<Fp, 4>::new([9, 20, 5, 12]);
+        prove_and_verify(min_k(&circuit).unwrap(), circuit, &[instance]).unwrap();
+    }
+
+    // $ cargo test --release --all-features plot_batch
+    #[cfg(feature = "dev-graph")]
+    #[test]
+    fn plot_batch() {
+        use plotters::prelude::*;
+
+        for (name, file) in [
+            ("Parallel", "batch-parallel-layout.png"),
+            ("Stacked", "batch-stacked-layout.png"),
+        ] {
+            let root = BitMapBackend::new(file, (1024, 3096)).into_drawing_area();
+            root.fill(&WHITE).unwrap();
+            let root = root
+                .titled(&format!("{name} Batch Layout"), ("sans-serif", 60))
+                .unwrap();
+
+            let layout = halo2_proofs::dev::CircuitLayout::default();
+            if name == "Parallel" {
+                let circuit = ParallelBatchCircuit::<Fp, 4>::new([9, 20, 5, 12]);
+                layout
+                    .render(min_k(&circuit).unwrap(), &circuit, &root)
+                    .unwrap();
+            } else {
+                let circuit = StackedBatchCircuit::<Fp, 4>::new([9, 20, 5, 12]);
+                layout
+                    .render(min_k(&circuit).unwrap(), &circuit, &root)
+                    .unwrap();
+            }
+        }
+    }
+}
@@ -3,17 +3,17 @@ use ff::{Field, PrimeField};
 use halo2_proofs::{circuit::*, plonk::*, poly::Rotation};
 
 #[derive(Debug, Clone)]
-struct ACell<F: PrimeField>(AssignedCell<F, F>);
+pub struct ACell<F: PrimeField>(pub AssignedCell<F, F>);
 
 #[derive(Debug, Clone)]
-struct FiboConfig {
+pub struct FiboConfig {
     pub advice: [Column<Advice>; 3],
     pub selector: Selector,
     pub instance: Column<Instance>,
 }
 
 #[derive(Debug, Clone)]
-struct FiboChip<F: PrimeField> {
+pub struct FiboChip<F: PrimeField> {
     config: FiboConfig,
     _marker: PhantomData<F>,
 }
@@ -102,6 +102,35 @@ impl<F: PrimeField> FiboChip<F> {
         )
     }
 
+    /// Same as `assign_first_row`, but the seeds are circuit constants instead of
+    /// instance rows 0 and 1. Needs a column enabled with `meta.enable_constant`.
+    #[allow(clippy::type_complexity)]
+    pub fn assign_first_row_from_constants(
+        &self,
+        mut layouter: impl Layouter<F>,
+        a: F,
+        b: F,
+    ) -> Result<(ACell<F>, ACell<F>, ACell<F>), Error> {
+        layouter.assign_region(
+            || "first row",
+            |mut region| {
+                self.config.selector.enable(&mut region, 0)?;
+
+                let a_cell = region
+                    .assign_advice_from_constant(|| "f(0)", self.config.advice[0], 0, a)
+                    .map(ACell)?;
+                let b_cell = region
+                    .assign_advice_from_constant(|| "f(1)", self.config.advice[1], 0, b)
+                    .map(ACell)?;
+                let c_cell = region
+                    .assign_advice(|| "a + b", self.config.advice[2], 0, || Value::known(a + b))
+                    .map(ACell)?;
+
+                Ok((a_cell, b_cell, c_cell))
+            },
+        )
+    }
+
     pub fn assign_row(
         &self,  // 当前`FiboChip`实例的引用
         mut layouter: impl Layouter<F>,
@@ -132,6 +161,29 @@ impl<F: PrimeField> FiboChip<F> {
         )
     }
 
+    /// Continues from the first row up to `f(n)` and returns its cell.
+    pub fn assign_steps(
+        &self,
+        mut layouter: impl Layouter<F>,
+        (prev_a, mut prev_b, mut prev_c): (ACell<F>, ACell<F>, ACell<F>),
+        n: usize,
+    ) -> Result<ACell<F>, Error> {
+        match n {
+            0 => Ok(prev_a),
+            1 => Ok(prev_b),
+            _ => {
+                // the first row already holds f(2), one more row per step up to f(n)
+                for _i in 3..=n {
+                    let c_cell =
+                        self.assign_row(layouter.namespace(|| "next row"), &prev_b, &prev_c)?;
+                    prev_b = prev_c;
+                    prev_c = c_cell;
+                }
+                Ok(prev_c)
+            }
+        }
+    }
+
     pub fn expose_public(
         &self,
         mut layouter: impl Layouter<F>,
@@ -142,15 +194,46 @@ impl<F: PrimeField> FiboChip<F> {
     }
 }
 
-#[derive(Default)]
-struct MyCircuit<F> (PhantomData<F>);
+/// Computes the `n`-th term of the sequence seeded with `f(0) = a` and `f(1) = b`.
+pub fn nth_term<F: Field>(a: F, b: F, n: usize) -> F {
+    let (mut prev, mut cur) = (a, b);
+    if n == 0 {
+        return prev;
+    }
+    for _ in 1..n {
+        let next = prev + cur;
+        prev = cur;
+        cur = next;
+    }
+    cur
+}
+
+/// Proves `f(n) = out` for the public instance `[f(0), f(1), out]`.
+///
+/// The first row computes `f(2)`, every further step adds one row, so the circuit
+/// uses `max(n, 2) - 1` rows and `n` must fit into the usable rows of `2^k`.
+#[derive(Debug, Clone, Default)]
+pub struct FibonacciCircuit<F> {
+    pub n: usize,
+    _marker: PhantomData<F>,
+}
 
-impl<F: PrimeField> Circuit<F> for MyCircuit<F> {
+impl<F> FibonacciCircuit<F> {
+    pub fn new(n: usize) -> Self {
+        Self {
+            n,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<F: PrimeField> Circuit<F> for FibonacciCircuit<F> {
     type Config = FiboConfig;
     type FloorPlanner = SimpleFloorPlanner;
 
+    // `n` fixes the shape of the circuit, so it is kept when the witnesses are dropped.
     fn without_witnesses(&self) -> Self {
-        Self::default()
+        Self::new(self.n)
     }
 
     fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
@@ -168,20 +251,58 @@ impl<F: PrimeField> Circuit<F> for MyCircuit<F> {
     ) -> Result<(), Error> {
         let chip = FiboChip::construct(config);
 
-        let (_, mut prev_b, mut prev_c) =
-            chip.assign_first_row(layouter.namespace(|| "first row"))?;
-        
-        // 这是干啥??
-        // chip.expose_public(layouter.namespace(|| "private a"), &prev_a, 0)?;
-        // chip.expose_public(layouter.namespace(|| "private b"), &prev_b, 1)?;
-
-        for _i in 3..10 {
-            let c_cell = chip.assign_row(layouter.namespace(|| "next row"), &prev_b, &prev_c)?;
-            prev_b = prev_c;
-            prev_c = c_cell;
-        }
+        let first_row = chip.assign_first_row(layouter.namespace(|| "first row"))?;
+        let out = chip.assign_steps(layouter.namespace(|| "steps"), first_row, self.n)?;
 
-        chip.expose_public(layouter.namespace(|| "out"), &prev_c, 2)?;
+        chip.expose_public(layouter.namespace(|| "out"), &out, 2)?;
+
+        Ok(())
+    }
+}
+
+/// Proves `f(n) = out` for the public instance `[out]`, with the seeds `f(0)` and
+/// `f(1)` fixed as circuit constants.
+///
+/// The seeds end up in a fixed column, so every seed choice is a different circuit
+/// with its own verifying key.
+#[derive(Debug, Clone, Default)]
+pub struct ConstantSeedFibonacciCircuit<F> {
+    pub seeds: (F, F),
+    pub n: usize,
+}
+
+impl<F: PrimeField> Circuit<F> for ConstantSeedFibonacciCircuit<F> {
+    type Config = FiboConfig;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    // the seeds and `n` fix the circuit, there is no witness to drop
+    fn without_witnesses(&self) -> Self {
+        self.clone()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let col_a = meta.advice_column();
+        let col_b = meta.advice_column();
+        let col_c = meta.advice_column();
+        let instance = meta.instance_column();
+        let constant = meta.fixed_column();
+        meta.enable_constant(constant);
+        FiboChip::configure(meta, [col_a, col_b, col_c], instance)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = FiboChip::construct(config);
+
+        let (a, b) = self.seeds;
+        let first_row =
+            chip.assign_first_row_from_constants(layouter.namespace(|| "first row"), a, b)?;
+        let out = chip.assign_steps(layouter.namespace(|| "steps"), first_row, self.n)?;
+
+        chip.expose_public(layouter.namespace(|| "out"), &out, 0)?;
 
         Ok(())
     }
@@ -189,19 +310,26 @@ impl<F: PrimeField> Circuit<F> for MyCircuit<F> {
 
 #[cfg(test)]
 mod tests {
-    use super::MyCircuit;
-    use halo2_proofs::{circuit::Value, dev::MockProver, pasta::Fp};
-    use std::marker::PhantomData;
+    use super::{nth_term, ConstantSeedFibonacciCircuit, FibonacciCircuit};
+    use crate::{
+        proof::{keygen, prove, setup, verify},
+        rows::min_k,
+    };
+    use ff::Field;
+    use halo2_proofs::{
+        dev::MockProver,
+        pasta::{EqAffine, Fp},
+        plonk::{Error, ProvingKey},
+    };
 
     #[test]
     fn test_example1() {
-        let k = 4;
-
         let a = Fp::from(1); // F[0]
         let b = Fp::from(1); // F[1]
         let out = Fp::from(55); // F[9]
 
-        let circuit = MyCircuit(PhantomData);
+        let circuit = FibonacciCircuit::new(9);
+        let k = min_k(&circuit).unwrap();
 
         let mut public_input = vec![a, b, out];
 
@@ -209,9 +337,126 @@ mod tests {
         prover.assert_satisfied();
 
         public_input[2] += Fp::one(); // out += 2  =>  unsatisfied
-        let _prover = MockProver::run(k, &circuit, vec![public_input]).unwrap();
-        // uncomment the following line and the assert will fail
-        // _prover.assert_satisfied();
+        let prover = MockProver::run(k, &circuit, vec![public_input]).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn test_example1_real_prover() {
+        let circuit = FibonacciCircuit::<Fp>::new(9);
+        let k = min_k(&circuit).unwrap();
+        let mut public_input = vec![Fp::from(1), Fp::from(1), Fp::from(55)];
+
+        let (params, pk) = setup(k, &circuit).unwrap();
+        let proof = prove(&params, &pk, circuit.clone(), &[public_input.clone()]).unwrap();
+        verify(&params, pk.get_vk(), &[public_input.clone()], &proof).unwrap();
+
+        // the honest proof does not verify against a wrong output ...
+        public_input[2] += Fp::one();
+        assert!(verify(&params, pk.get_vk(), &[public_input.clone()], &proof).is_err());
+
+        // ... and a proof created for the wrong output is rejected as well
+        let proof = prove(&params, &pk, circuit, &[public_input.clone()]).unwrap();
+        assert!(verify(&params, pk.get_vk(), &[public_input], &proof).is_err());
+    }
+
+    #[test]
+    fn test_example1_steps() {
+        let seeds = [(1, 1), (0, 1), (2, 1), (5, 7)];
+
+        for n in [0, 1, 2, 3, 9, 20, 27] {
+            let circuit = FibonacciCircuit::new(n);
+            let k = min_k(&circuit).unwrap();
+
+            for (a, b) in seeds {
+                let (a, b) = (Fp::from(a), Fp::from(b));
+                let out = nth_term(a, b, n);
+
+                let prover = MockProver::run(k, &circuit, vec![vec![a, b, out]]).unwrap();
+                prover.assert_satisfied();
+
+                let prover =
+                    MockProver::run(k, &circuit, vec![vec![a, b, out + Fp::ONE]]).unwrap();
+                assert!(prover.verify().is_err(), "n = {n} accepted a wrong output");
+            }
+        }
+    }
+
+    #[test]
+    fn test_example1_too_many_steps() {
+        // 2^4 rows leave 10 usable rows, f(12) needs 11 of them.
+        let circuit = FibonacciCircuit::<Fp>::new(12);
+        let instance = vec![Fp::one(), Fp::one(), nth_term(Fp::one(), Fp::one(), 12)];
+        assert!(matches!(
+            MockProver::run(4, &circuit, vec![instance]),
+            Err(Error::NotEnoughRowsAvailable { .. })
+        ));
+    }
+
+    #[test]
+    fn test_nth_term() {
+        let terms: Vec<_> = (0..10).map(|n| nth_term(Fp::one(), Fp::one(), n)).collect();
+        let expected: Vec<_> = [1u64, 1, 2, 3, 5, 8, 13, 21, 34, 55]
+            .into_iter()
+            .map(Fp::from)
+            .collect();
+        assert_eq!(terms, expected);
+    }
+
+    #[test]
+    fn test_example1_constant_seeds() {
+        // (f(0), f(1)), n, f(n)
+        let cases = [
+            ((1, 1), 9, 55),
+            ((2, 1), 9, 76),
+            ((0, 1), 10, 55),
+            ((5, 7), 0, 5),
+            ((5, 7), 1, 7),
+        ];
+        for ((a, b), n, out) in cases {
+            let circuit = ConstantSeedFibonacciCircuit {
+                seeds: (Fp::from(a), Fp::from(b)),
+                n,
+            };
+            let k = min_k(&circuit).unwrap();
+
+            let prover = MockProver::run(k, &circuit, vec![vec![Fp::from(out)]]).unwrap();
+            prover.assert_satisfied();
+
+            let prover = MockProver::run(k, &circuit, vec![vec![Fp::from(out + 1)]]).unwrap();
+            assert!(prover.verify().is_err());
+        }
+    }
+
+    #[test]
+    fn test_example1_constant_seeds_vk() {
+        let fibonacci = ConstantSeedFibonacciCircuit {
+            seeds: (Fp::one(), Fp::one()),
+            n: 9,
+        };
+        let lucas = ConstantSeedFibonacciCircuit {
+            seeds: (Fp::from(2), Fp::one()),
+            n: 9,
+        };
+        let k = min_k(&fibonacci).unwrap();
+        let pinned = |pk: &ProvingKey<EqAffine>| format!("{:?}", pk.get_vk().pinned());
+
+        // the seeds are part of the verifying key
+        let (params, pk_fibonacci) = setup(k, &fibonacci).unwrap();
+        let pk_lucas = keygen(&params, &lucas).unwrap();
+        assert_ne!(pinned(&pk_fibonacci), pinned(&pk_lucas));
+        assert_eq!(pinned(&pk_fibonacci), pinned(&keygen(&params, &fibonacci).unwrap()));
+
+        // a proof for one seed choice does not carry over to the other
+        let instance = [vec![Fp::from(55)]];
+        let proof = prove(&params, &pk_fibonacci, fibonacci, &instance).unwrap();
+        verify(&params, pk_fibonacci.get_vk(), &instance, &proof).unwrap();
+        assert!(verify(&params, pk_lucas.get_vk(), &instance, &proof).is_err());
+
+        let instance = [vec![Fp::from(76)]];
+        let proof = prove(&params, &pk_lucas, lucas, &instance).unwrap();
+        verify(&params, pk_lucas.get_vk(), &instance, &proof).unwrap();
+        assert!(verify(&params, pk_fibonacci.get_vk(), &instance, &proof).is_err());
     }
 
     // $ cargo test --release --all-features plot_fibo1
@@ -224,9 +469,9 @@ mod tests {
         root.fill(&WHITE).unwrap();
         let root = root.titled("Fib 1 Layout", ("sans-serif", 60)).unwrap();
 
-        let circuit = MyCircuit::<Fp>(PhantomData);
+        let circuit = FibonacciCircuit::<Fp>::new(9);
         halo2_proofs::dev::CircuitLayout::default()
-            .render(4, &circuit, &root)
+            .render(min_k(&circuit).unwrap(), &circuit, &root)
             .unwrap();
     }
-}
\ No newline at end of file
+}
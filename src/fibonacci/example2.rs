@@ -3,17 +3,17 @@ use ff::{Field, PrimeField};
 use halo2_proofs::{circuit::*, plonk::*, poly::Rotation};
 
 #[derive(Debug, Clone)]
-struct ACell<F: PrimeField>(AssignedCell<F, F>);
+pub struct ACell<F: PrimeField>(pub AssignedCell<F, F>);
 
 #[derive(Debug, Clone)]
-struct FiboConfig {
+pub struct FiboConfig {
     advice: Column<Advice>,
     selector: Selector,
     instance: Column<Instance>,
 }
 
 #[derive(Debug, Clone)]
-struct FiboChip<F: PrimeField> {
+pub struct FiboChip<F: PrimeField> {
     config: FiboConfig,
     _marker: PhantomData<F>,
 }
@@ -63,8 +63,18 @@ impl<F: PrimeField> FiboChip<F> {
 
     pub fn assign(
         &self,
-        mut layouter: impl Layouter<F>,
+        layouter: impl Layouter<F>,
         nrows: usize,  // 前 2 列赋值之后, 后面要搞的列数.. 
+    ) -> Result<ACell<F>, Error> {
+        self.assign_seeded(layouter, 0, nrows)
+    }
+
+    /// Same as `assign`, with the seeds taken from instance rows `seed_row` and `seed_row + 1`.
+    pub fn assign_seeded(
+        &self,
+        mut layouter: impl Layouter<F>,
+        seed_row: usize,
+        nrows: usize,
     ) -> Result<ACell<F>, Error> {
         layouter.assign_region(
             || "entire fibonacci table",
@@ -79,7 +89,7 @@ impl<F: PrimeField> FiboChip<F> {
                 let mut a_cell = region.assign_advice_from_instance(
                     || "1",
                     self.config.instance,
-                    0,  // instance column's row 0
+                    seed_row,  // instance column's row `seed_row`
                     self.config.advice,
                     0, // 复制到当前的 region 的 row 0
                 ).map(ACell)?;
@@ -87,7 +97,7 @@ impl<F: PrimeField> FiboChip<F> {
                 let mut b_cell = region.assign_advice_from_instance(
                     || "1",
                     self.config.instance,
-                    1, // instance column's row 1
+                    seed_row + 1, // the row after it
                     self.config.advice,
                     1,  // 复制到当前的 region 的 row 1
                 ).map(ACell)?;
@@ -124,53 +134,72 @@ impl<F: PrimeField> FiboChip<F> {
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use halo2_proofs::{dev::MockProver, pasta::Fp};
+/// Proves `f(n) = out` for the public instance `[f(0), f(1), out]` with the whole
+/// sequence `f(0)..=f(n)` in a single advice column, so `n + 1` rows are used.
+///
+/// The gate at row `i` reaches down to row `i + 2`, so at least `n = 3` is needed.
+#[derive(Debug, Clone, Default)]
+pub struct FibonacciCircuit<F> {
+    pub n: usize,
+    _marker: PhantomData<F>,
+}
 
-    #[derive(Default)]
-    struct MyCircuit<F>(PhantomData<F>);
+impl<F> FibonacciCircuit<F> {
+    pub fn new(n: usize) -> Self {
+        Self {
+            n,
+            _marker: PhantomData,
+        }
+    }
+}
 
-    impl<F: PrimeField> Circuit<F> for MyCircuit<F> {
-        type Config = FiboConfig;
-        type FloorPlanner = SimpleFloorPlanner;
+impl<F: PrimeField> Circuit<F> for FibonacciCircuit<F> {
+    type Config = FiboConfig;
+    type FloorPlanner = SimpleFloorPlanner;
 
-        fn without_witnesses(&self) -> Self {
-            Self::default()
-        }
+    fn without_witnesses(&self) -> Self {
+        Self::new(self.n)
+    }
 
-        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
-            let advice = meta.advice_column();
-            let instance = meta.instance_column();
-            FiboChip::configure(meta, advice, instance)
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let advice = meta.advice_column();
+        let instance = meta.instance_column();
+        FiboChip::configure(meta, advice, instance)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        if self.n < 3 {
+            return Err(Error::Synthesis);
         }
+        let chip = FiboChip::construct(config);
 
-        fn synthesize(
-            &self,
-            config: Self::Config,
-            mut layouter: impl Layouter<F>,
-        ) -> Result<(), Error> {
-            let chip = FiboChip::construct(config);
-            
-            // entire table's last cell is the output of 10 times Fibonacci.
-            let out_cell = chip.assign(layouter.namespace(|| "entire table"), 10)?;
+        // entire table's last cell is f(n), e.g. 10 rows end with f(9).
+        let out_cell = chip.assign(layouter.namespace(|| "entire table"), self.n + 1)?;
 
-            chip.expose_public(layouter.namespace(|| "out"), out_cell, 2)?;
+        chip.expose_public(layouter.namespace(|| "out"), out_cell, 2)?;
 
-            Ok(())
-        }
+        Ok(())
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{proof::prove_and_verify, rows::min_k};
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
 
     #[test]
     fn test_example2() {
-        let k = 4;
-
         let a = Fp::from(1); // F[0]
         let b = Fp::from(1); // F[1]
         let out = Fp::from(55); // F[9]
 
-        let circuit = MyCircuit(PhantomData);
+        let circuit = FibonacciCircuit::new(9);
+        let k = min_k(&circuit).unwrap();
 
         let mut public_input = vec![a, b, out];
 
@@ -183,6 +212,18 @@ mod tests {
         // _prover.assert_satisfied();
     }
 
+    #[test]
+    fn test_example2_real_prover() {
+        let circuit = FibonacciCircuit::<Fp>::new(9);
+        let k = min_k(&circuit).unwrap();
+        let mut public_input = vec![Fp::from(1), Fp::from(1), Fp::from(55)];
+
+        prove_and_verify(k, circuit.clone(), &[public_input.clone()]).unwrap();
+
+        public_input[2] += Fp::one();
+        assert!(prove_and_verify(k, circuit, &[public_input]).is_err());
+    }
+
     // $ cargo test --release --all-features plot_fibo2
     #[cfg(feature = "dev-graph")]
     #[test]
@@ -192,9 +233,9 @@ mod tests {
         root.fill(&WHITE).unwrap();
         let root = root.titled("Fib 2 Layout", ("sans-serif", 60)).unwrap();
 
-        let circuit = MyCircuit::<Fp>(PhantomData);
+        let circuit = FibonacciCircuit::<Fp>::new(9);
         halo2_proofs::dev::CircuitLayout::default()
-            .render(4, &circuit, &root)
+            .render(min_k(&circuit).unwrap(), &circuit, &root)
             .unwrap();
     }
 }
\ No newline at end of file
@@ -1,23 +1,26 @@
-use crate::is_zero::{IsZeroChip, IsZeroConfig};
-use ff::{Field, PrimeField};
+use crate::piecewise::{Condition, Expr, Input, Piecewise, PiecewiseChip, PiecewiseConfig};
+use ff::PrimeField;
 use halo2_proofs::{
     circuit::{AssignedCell, Layouter, SimpleFloorPlanner, Value},
-    plonk::{Advice, Circuit, Column, ConstraintSystem, Error, Expression, Selector},
-    poly::Rotation,
+    plonk::{Circuit, Column, ConstraintSystem, Error, Instance},
 };
 
+/// `f(a, b, c) = if a == b {c} else {a - b}`, computed outside the circuit.
+pub fn function<F: PrimeField>(a: F, b: F, c: F) -> F {
+    if a == b {
+        c
+    } else {
+        a - b
+    }
+}
+
 #[derive(Debug, Clone)]
-struct FunctionConfig<F: PrimeField> {
-    selector: Selector,
-    a: Column<Advice>,
-    b: Column<Advice>,
-    c: Column<Advice>,
-    a_equals_b: IsZeroConfig<F>,
-    output: Column<Advice>,
+pub struct FunctionConfig<F: PrimeField> {
+    function: PiecewiseConfig<F, 3>,
 }
 
 #[derive(Debug, Clone)]
-struct FunctionChip<F: PrimeField> {
+pub struct FunctionChip<F: PrimeField> {
     config: FunctionConfig<F>,
 }
 
@@ -27,83 +30,44 @@ impl<F: PrimeField> FunctionChip<F> {
     }
 
     pub fn configure(meta: &mut ConstraintSystem<F>) -> FunctionConfig<F> {
-        let selector = meta.selector();
-        let a = meta.advice_column();
-        let b = meta.advice_column();
-        let c = meta.advice_column();
+        let inputs = [(); 3].map(|_| meta.advice_column());
         let output = meta.advice_column();
 
-        let is_zero_advice_column = meta.advice_column();
-
-        // apply on column, not cell ; cell's assignments base on `fn assign()`
-        //   returns IsZeroConfig<F>
-        let a_equals_b: IsZeroConfig<F> = IsZeroChip::configure(
-            meta,
-            |meta| meta.query_selector(selector),
-            |meta| meta.query_advice(a, Rotation::cur()) - meta.query_advice(b, Rotation::cur()),
-            is_zero_advice_column,
-        );
-
-        meta.create_gate("f(a, b, c) = if a == b {c} else {a - b}", |meta| {
-            let sel = meta.query_selector(selector);
-            let a = meta.query_advice(a, Rotation::cur());
-            let b = meta.query_advice(b, Rotation::cur());
-            let c = meta.query_advice(c, Rotation::cur());
-            let output = meta.query_advice(output, Rotation::cur());
-            
-            // both constrants need to be satisfied. (both need equal to 0)
-            vec![
-                sel.clone() * (a_equals_b.expr() * (output.clone() - c)),
-                sel * (Expression::Constant(F::ONE) - a_equals_b.expr()) * (output - (a - b)),
-            ]
-        });
+        // f(a, b, c) = if a == b {c} else {a - b}
+        //   the a == b test (inverse column + IsZeroChip) and the gate come from the builder
+        let [a, b, c] = Expr::vars();
+        let function = Piecewise::when(Condition::equal(a.clone(), b.clone()), c).otherwise(a - b);
 
         FunctionConfig {
-            selector,
-            a,
-            b,
-            c,
-            a_equals_b,
-            output,
+            function: PiecewiseChip::configure(meta, function, inputs, output),
         }
     }
 
+    /// Each input is either a witness (`Value<F>`) or a cell copied from elsewhere
+    /// (`AssignedCell`), so calls can be chained. Returns the output cell.
     pub fn assign(
         &self,
-        mut layouter: impl Layouter<F>,
-        a: F,
-        b: F,
-        c: F,
+        layouter: impl Layouter<F>,
+        a: impl Into<Input<F>>,
+        b: impl Into<Input<F>>,
+        c: impl Into<Input<F>>,
     ) -> Result<AssignedCell<F, F>, Error> {
-        let is_zero_chip = IsZeroChip::construct(self.config.a_equals_b.clone());
-
-        layouter.assign_region(
-            || "f(a, b, c) = if a == b {c} else {a - b}",
-            |mut region| {
-                self.config.selector.enable(&mut region, 0)?;
-                region.assign_advice(|| "a", self.config.a, 0, || Value::known(a))?;
-                region.assign_advice(|| "b", self.config.b, 0, || Value::known(b))?;
-                region.assign_advice(|| "c", self.config.c, 0, || Value::known(c))?;
-
-                // 使用 IsZeroChip 子电路来检查 a - b 是否为零, 从而判断 `a ?= b`
-                is_zero_chip.assign(&mut region, 0, Value::known(a - b))?;
-
-                let output = if a == b { c } else { a - b }; // Rust expr to calculate val.
-                region.assign_advice(|| "output", self.config.output, 0, || Value::known(output))
-            },
-        )
+        PiecewiseChip::construct(self.config.function.clone())
+            .assign(layouter, [a.into(), b.into(), c.into()])
     }
 }
 
-#[derive(Default)]
-struct FunctionCircuit<F> {
-    a: F,
-    b: F,
-    c: F,
+/// Computes `f(a, b, c) = if a == b {c} else {a - b}` over private inputs, the output
+/// is the public instance `[f(a, b, c)]`.
+#[derive(Debug, Clone, Default)]
+pub struct FunctionCircuit<F> {
+    pub a: Value<F>,
+    pub b: Value<F>,
+    pub c: Value<F>,
 }
 
 impl<F: PrimeField> Circuit<F> for FunctionCircuit<F> {
-    type Config = FunctionConfig<F>;
+    type Config = (FunctionConfig<F>, Column<Instance>);
     type FloorPlanner = SimpleFloorPlanner;
 
     fn without_witnesses(&self) -> Self {
@@ -111,31 +75,128 @@ impl<F: PrimeField> Circuit<F> for FunctionCircuit<F> {
     }
 
     fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
-        FunctionChip::configure(meta)
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+        (FunctionChip::configure(meta), instance)
     }
 
-    fn synthesize(&self, config: Self::Config, layouter: impl Layouter<F>) -> Result<(), Error> {
+    fn synthesize(
+        &self,
+        (config, instance): Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
         let chip = FunctionChip::construct(config);
-        chip.assign(layouter, self.a, self.b, self.c)?;
-        Ok(())
+        let out = chip.assign(layouter.namespace(|| "f"), self.a, self.b, self.c)?;
+        layouter.constrain_instance(out.cell(), instance, 0)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{proof::prove_and_verify, rows::min_k};
     use halo2_proofs::{dev::MockProver, pasta::Fp};
 
+    fn circuit(a: u64, b: u64, c: u64) -> FunctionCircuit<Fp> {
+        FunctionCircuit {
+            a: Value::known(Fp::from(a)),
+            b: Value::known(Fp::from(b)),
+            c: Value::known(Fp::from(c)),
+        }
+    }
+
     #[test]
     fn test_example3() {
-        let circuit = FunctionCircuit {
-            a: Fp::from(10),
-            b: Fp::from(12),
-            c: Fp::from(15),
-        };
+        let k = min_k(&FunctionCircuit::<Fp>::default()).unwrap();
+        for (a, b, c, out) in [(10, 12, 15, -Fp::from(2)), (7, 7, 3, Fp::from(3))] {
+            let circuit = circuit(a, b, c);
+            assert_eq!(function(Fp::from(a), Fp::from(b), Fp::from(c)), out);
+
+            let prover = MockProver::run(k, &circuit, vec![vec![out]]).unwrap();
+            prover.assert_satisfied();
+
+            // the output is public, a wrong claim fails
+            let prover = MockProver::run(k, &circuit, vec![vec![out + Fp::one()]]).unwrap();
+            assert!(prover.verify().is_err());
+        }
+    }
 
-        let prover = MockProver::run(4, &circuit, vec![]).unwrap();
-        prover.assert_satisfied();
+    #[test]
+    fn test_example3_real_prover() {
+        for (a, b, c) in [(10, 12, 15), (7, 7, 3)] {
+            let out = function(Fp::from(a), Fp::from(b), Fp::from(c));
+            let circuit = circuit(a, b, c);
+            let k = min_k(&circuit).unwrap();
+            prove_and_verify(k, circuit, &[vec![out]]).unwrap();
+        }
+    }
+
+    // f(f(a, b, c), d, e), the first output is copied into the second call
+    #[derive(Default)]
+    struct ChainCircuit {
+        a: Value<Fp>,
+        b: Value<Fp>,
+        c: Value<Fp>,
+        d: Value<Fp>,
+        e: Value<Fp>,
+    }
+
+    impl Circuit<Fp> for ChainCircuit {
+        type Config = (FunctionConfig<Fp>, Column<Instance>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            FunctionCircuit::<Fp>::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            (config, instance): Self::Config,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            let chip = FunctionChip::construct(config);
+            let inner = chip.assign(layouter.namespace(|| "inner"), self.a, self.b, self.c)?;
+            layouter.constrain_instance(inner.cell(), instance, 0)?;
+            let outer = chip.assign(layouter.namespace(|| "outer"), &inner, self.d, self.e)?;
+            layouter.constrain_instance(outer.cell(), instance, 1)
+        }
+    }
+
+    #[test]
+    fn test_example3_chained() {
+        let k = min_k(&ChainCircuit::default()).unwrap();
+        for inputs in [[10, 12, 15, 3, 9], [7, 7, 3, 3, 9], [5, 1, 0, 2, 8]] {
+            let [a, b, c, d, e] = inputs.map(Fp::from);
+            let inner = function(a, b, c);
+            let outer = function(inner, d, e);
+            let circuit = ChainCircuit {
+                a: Value::known(a),
+                b: Value::known(b),
+                c: Value::known(c),
+                d: Value::known(d),
+                e: Value::known(e),
+            };
+
+            let prover = MockProver::run(k, &circuit, vec![vec![inner, outer]]).unwrap();
+            prover.assert_satisfied();
+
+            let wrong = vec![vec![inner, outer + Fp::one()]];
+            let prover = MockProver::run(k, &circuit, wrong).unwrap();
+            assert!(prover.verify().is_err());
+        }
+
+        let circuit = ChainCircuit {
+            a: Value::known(Fp::from(7)),
+            b: Value::known(Fp::from(7)),
+            c: Value::known(Fp::from(3)),
+            d: Value::known(Fp::from(3)),
+            e: Value::known(Fp::from(9)),
+        };
+        prove_and_verify(k, circuit, &[vec![Fp::from(3), Fp::from(9)]]).unwrap();
     }
 
     // $ cargo test --release --all-features plot_fibo3
@@ -155,13 +216,9 @@ mod tests {
         //     .render(4, &circuit, &root)
         //     .unwrap();
 
-        let circuit = FunctionCircuit::<Fp>{
-            a: Fp::ZERO,
-            b: Fp::ZERO,
-            c: Fp::ZERO,
-        };
+        let circuit = FunctionCircuit::<Fp>::default();
         halo2_proofs::dev::CircuitLayout::default()
-            .render(4, &circuit, &root)
+            .render(min_k(&circuit).unwrap(), &circuit, &root)
             .unwrap();
     }
 }
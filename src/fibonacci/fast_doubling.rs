@@ -0,0 +1,346 @@
+use std::marker::PhantomData;
+
+use ff::{Field, PrimeField};
+use halo2_proofs::{circuit::*, plonk::*, poly::Rotation};
+
+use crate::range_check::example1::RangeCheckConfig;
+
+// Fast doubling: with m the index read so far and (a, b) = (F(m), F(m+1)),
+//
+//     F(2m)   = F(m)·(2F(m+1) - F(m))   =: d0
+//     F(2m+1) = F(m)^2 + F(m+1)^2       =: d1
+//
+// so every bit of n (MSB first) takes one row, and F(n) mod p needs BITS + 1 rows
+// instead of n:
+//
+//    a     |  b       | bit | index | q_step
+//   F(0)=0 |  F(1)=1  | b_0 |   0   |   1
+//   F(m)   |  F(m+1)  | b_1 |   m   |   1
+//   ...    |  ...     | ... |  ...  |  ...
+//   F(n)   |  F(n+1)  |     |   n   |   0
+//
+//   a'     = d0 + bit·(d1 - d0)
+//   b'     = d1 + bit·d0
+//   index' = 2·index + bit
+//
+// Every bit is witnessed once more in a separate column and checked to be in 0..2
+// by `range_check::example1::RangeCheckConfig`, then copied into `bit`.
+
+#[derive(Debug, Clone)]
+pub struct FastFiboConfig<F: PrimeField> {
+    a: Column<Advice>,
+    b: Column<Advice>,
+    bit: Column<Advice>,
+    index: Column<Advice>,
+    q_step: Selector,
+    bit_check: RangeCheckConfig<F, 2>,
+    instance: Column<Instance>,
+}
+
+#[derive(Debug, Clone)]
+pub struct FastFiboChip<F: PrimeField, const BITS: usize> {
+    config: FastFiboConfig<F>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField, const BITS: usize> FastFiboChip<F, BITS> {
+    pub fn construct(config: FastFiboConfig<F>) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        [a, b, bit, index]: [Column<Advice>; 4],
+        bit_check: Column<Advice>,
+        constant: Column<Fixed>,
+        instance: Column<Instance>,
+    ) -> FastFiboConfig<F> {
+        let q_step = meta.selector();
+
+        meta.enable_constant(constant);
+        for column in [a, b, bit, index, bit_check] {
+            meta.enable_equality(column);
+        }
+        meta.enable_equality(instance);
+
+        let bit_check = RangeCheckConfig::configure(meta, bit_check);
+
+        meta.create_gate("fast doubling", |meta| {
+            let q = meta.query_selector(q_step);
+            let a_cur = meta.query_advice(a, Rotation::cur());
+            let b_cur = meta.query_advice(b, Rotation::cur());
+            let bit = meta.query_advice(bit, Rotation::cur());
+            let index_cur = meta.query_advice(index, Rotation::cur());
+            let a_next = meta.query_advice(a, Rotation::next());
+            let b_next = meta.query_advice(b, Rotation::next());
+            let index_next = meta.query_advice(index, Rotation::next());
+
+            let two = Expression::Constant(F::from(2));
+            let d0 = a_cur.clone() * (two.clone() * b_cur.clone() - a_cur.clone());
+            let d1 = a_cur.clone() * a_cur + b_cur.clone() * b_cur;
+
+            Constraints::with_selector(
+                q,
+                [
+                    (
+                        "a = F(2m + bit)",
+                        a_next - (d0.clone() + bit.clone() * (d1.clone() - d0.clone())),
+                    ),
+                    ("b = F(2m + bit + 1)", b_next - (d1 + bit.clone() * d0)),
+                    ("index = 2m + bit", index_next - (two * index_cur + bit)),
+                ],
+            )
+        });
+
+        FastFiboConfig {
+            a,
+            b,
+            bit,
+            index,
+            q_step,
+            bit_check,
+            instance,
+        }
+    }
+
+    /// Walks the `BITS` bits of `n` and returns the cells holding `n` and `F(n)`.
+    pub fn assign(
+        &self,
+        mut layouter: impl Layouter<F>,
+        n: Value<u64>,
+    ) -> Result<(AssignedCell<F, F>, AssignedCell<F, F>), Error> {
+        n.error_if_known_and(|n| BITS < 64 && n >> BITS != 0)?;
+
+        let bits = (0..BITS)
+            .rev()
+            .map(|i| {
+                let bit = n.map(|n| F::from(if i < 64 { (n >> i) & 1 } else { 0 }));
+                self.config.bit_check.assign(
+                    layouter.namespace(|| format!("bit {i}")),
+                    bit.map(Assigned::from),
+                )
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        layouter.assign_region(
+            || "fast doubling",
+            |mut region| {
+                let mut a =
+                    region.assign_advice_from_constant(|| "F(0)", self.config.a, 0, F::ZERO)?;
+                let mut b =
+                    region.assign_advice_from_constant(|| "F(1)", self.config.b, 0, F::ONE)?;
+                let mut index =
+                    region.assign_advice_from_constant(|| "m", self.config.index, 0, F::ZERO)?;
+
+                for (row, bit) in bits.iter().enumerate() {
+                    self.config.q_step.enable(&mut region, row)?;
+                    let bit = bit
+                        .0
+                        .copy_advice(|| "bit", &mut region, self.config.bit, row)?;
+                    let bit = bit.value().map(|bit| bit.evaluate());
+
+                    let d0 = a.value().zip(b.value()).map(|(a, b)| *a * (b.double() - a));
+                    let d1 = a
+                        .value()
+                        .zip(b.value())
+                        .map(|(a, b)| a.square() + b.square());
+                    let a_next = d0 + bit * (d1 - d0);
+                    let b_next = d1 + bit * d0;
+                    let index_next = index.value().zip(bit).map(|(m, bit)| m.double() + bit);
+
+                    a = region.assign_advice(|| "F(m)", self.config.a, row + 1, || a_next)?;
+                    b = region.assign_advice(|| "F(m+1)", self.config.b, row + 1, || b_next)?;
+                    index =
+                        region.assign_advice(|| "m", self.config.index, row + 1, || index_next)?;
+                }
+
+                Ok((index, a))
+            },
+        )
+    }
+
+    pub fn expose_public(
+        &self,
+        mut layouter: impl Layouter<F>,
+        cell: &AssignedCell<F, F>,
+        row: usize,
+    ) -> Result<(), Error> {
+        layouter.constrain_instance(cell.cell(), self.config.instance, row)
+    }
+}
+
+/// Computes `F(n)` natively with the same fast-doubling steps as [`FastFiboChip`].
+pub fn fast_nth_term<F: Field>(n: u64) -> F {
+    let (mut a, mut b) = (F::ZERO, F::ONE);
+    for i in (0..u64::BITS).rev() {
+        let d0 = a * (b.double() - a);
+        let d1 = a.square() + b.square();
+        (a, b) = if (n >> i) & 1 == 1 {
+            (d1, d0 + d1)
+        } else {
+            (d0, d1)
+        };
+    }
+    a
+}
+
+/// Proves `F(n) = out` for an `n` below `2^BITS`, in `BITS + 1` rows.
+///
+/// With `public_index` the instance is `[n, out]`, otherwise it is `[out]` and `n`
+/// stays private.
+#[derive(Debug, Clone, Default)]
+pub struct FastFibonacciCircuit<F, const BITS: usize> {
+    pub n: Value<u64>,
+    pub public_index: bool,
+    _marker: PhantomData<F>,
+}
+
+impl<F, const BITS: usize> FastFibonacciCircuit<F, BITS> {
+    pub fn new(n: Value<u64>, public_index: bool) -> Self {
+        Self {
+            n,
+            public_index,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<F: PrimeField, const BITS: usize> Circuit<F> for FastFibonacciCircuit<F, BITS> {
+    type Config = FastFiboConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    // whether `n` is public changes the copy constraints, so it is kept
+    fn without_witnesses(&self) -> Self {
+        Self::new(Value::unknown(), self.public_index)
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let advice = [(); 4].map(|_| meta.advice_column());
+        let bit_check = meta.advice_column();
+        let constant = meta.fixed_column();
+        let instance = meta.instance_column();
+        FastFiboChip::<F, BITS>::configure(meta, advice, bit_check, constant, instance)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = FastFiboChip::<F, BITS>::construct(config);
+        let (index, out) = chip.assign(layouter.namespace(|| "fast doubling"), self.n)?;
+
+        if self.public_index {
+            chip.expose_public(layouter.namespace(|| "n"), &index, 0)?;
+            chip.expose_public(layouter.namespace(|| "out"), &out, 1)
+        } else {
+            chip.expose_public(layouter.namespace(|| "out"), &out, 0)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{fibonacci::example1::nth_term, proof::prove_and_verify, rows::min_k};
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    fn instance(n: u64, out: Fp, public_index: bool) -> Vec<Vec<Fp>> {
+        if public_index {
+            vec![vec![Fp::from(n), out]]
+        } else {
+            vec![vec![out]]
+        }
+    }
+
+    #[test]
+    fn test_fast_doubling() {
+        for public_index in [true, false] {
+            for n in [0, 1, 2, 3, 10, 100, 200, 255] {
+                let circuit = FastFibonacciCircuit::<Fp, 8>::new(Value::known(n), public_index);
+                let k = min_k(&circuit).unwrap();
+                let out = nth_term(Fp::zero(), Fp::one(), n as usize);
+                assert_eq!(fast_nth_term::<Fp>(n), out);
+
+                let prover = MockProver::run(k, &circuit, instance(n, out, public_index)).unwrap();
+                prover.assert_satisfied();
+
+                let wrong = instance(n, out + Fp::one(), public_index);
+                let prover = MockProver::run(k, &circuit, wrong).unwrap();
+                assert!(prover.verify().is_err(), "n = {n} accepted a wrong output");
+            }
+        }
+
+        // a public index has to match the one walked in the circuit
+        let circuit = FastFibonacciCircuit::<Fp, 8>::new(Value::known(10), true);
+        let k = min_k(&circuit).unwrap();
+        let out = fast_nth_term(10);
+        let prover = MockProver::run(k, &circuit, instance(11, out, true)).unwrap();
+        assert!(prover.verify().is_err());
+    }
+
+    #[test]
+    fn test_fast_doubling_private_index() {
+        // F(1) = F(2) = 1, a private index only reveals the output
+        for n in [1, 2] {
+            let circuit = FastFibonacciCircuit::<Fp, 8>::new(Value::known(n), false);
+            let k = min_k(&circuit).unwrap();
+            let prover = MockProver::run(k, &circuit, vec![vec![Fp::one()]]).unwrap();
+            prover.assert_satisfied();
+        }
+    }
+
+    #[test]
+    fn test_fast_doubling_huge_index() {
+        let n = 1 << 40;
+        let circuit = FastFibonacciCircuit::<Fp, 41>::new(Value::known(n), true);
+
+        // 41 steps plus the final row
+        let k = min_k(&circuit).unwrap();
+        assert_eq!(k, 6);
+
+        let prover = MockProver::run(k, &circuit, instance(n, fast_nth_term(n), true)).unwrap();
+        prover.assert_satisfied();
+
+        // n does not fit into BITS bits
+        let circuit = FastFibonacciCircuit::<Fp, 8>::new(Value::known(256), true);
+        assert!(matches!(
+            MockProver::run(5, &circuit, instance(256, fast_nth_term(256), true)),
+            Err(Error::Synthesis)
+        ));
+    }
+
+    #[test]
+    fn test_fast_doubling_real_prover() {
+        let n = 1_000_000;
+        for public_index in [true, false] {
+            let circuit = FastFibonacciCircuit::<Fp, 20>::new(Value::known(n), public_index);
+            let k = min_k(&circuit).unwrap();
+            let out = fast_nth_term(n);
+
+            prove_and_verify(k, circuit.clone(), &instance(n, out, public_index)).unwrap();
+            let wrong = instance(n, out + Fp::one(), public_index);
+            assert!(prove_and_verify(k, circuit, &wrong).is_err());
+        }
+    }
+
+    // $ cargo test --release --all-features plot_fast_doubling
+    #[cfg(feature = "dev-graph")]
+    #[test]
+    fn plot_fast_doubling() {
+        use plotters::prelude::*;
+        let root = BitMapBackend::new("fast-doubling-layout.png", (1024, 3096)).into_drawing_area();
+        root.fill(&WHITE).unwrap();
+        let root = root
+            .titled("Fast Doubling Layout", ("sans-serif", 60))
+            .unwrap();
+
+        let circuit = FastFibonacciCircuit::<Fp, 8>::new(Value::unknown(), true);
+        halo2_proofs::dev::CircuitLayout::default()
+            .render(min_k(&circuit).unwrap(), &circuit, &root)
+            .unwrap();
+    }
+}
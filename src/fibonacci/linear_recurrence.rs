@@ -0,0 +1,407 @@
+use std::marker::PhantomData;
+
+use ff::{Field, PrimeField};
+use halo2_proofs::{circuit::*, plonk::*, poly::Rotation};
+
+// example2 generalized to any order-D linear recurrence
+//
+//     a(n) = c_1·a(n-1) + c_2·a(n-2) + ... + c_D·a(n-D)
+//
+// The whole sequence a(0)..=a(n) goes into a single advice column, the gate at row `r`
+// reaches down to `Rotation(D)`:
+//
+//   advice | selector | c_1 .. c_D (fixed, optional)
+//   a(0)   |    1     | c_1 .. c_D
+//   a(1)   |    1     | c_1 .. c_D
+//   ...    |   ...    |
+//   a(D)   |    0     |
+//
+//   Fibonacci, Lucas:  D = 2, c = [1, 1]        (seeds [0, 1] resp. [2, 1])
+//   Pell:              D = 2, c = [2, 1]        (seeds [0, 1])
+//   Tribonacci:        D = 3, c = [1, 1, 1]     (seeds [0, 0, 1])
+
+/// Where the coefficients `c_1..c_D` of the recurrence come from.
+#[derive(Debug, Clone)]
+pub enum Coefficients<F: PrimeField, const D: usize> {
+    /// Baked into the gate as constants, no extra columns.
+    Constant([F; D]),
+    /// Assigned into one fixed column per coefficient, next to every step.
+    Fixed([Column<Fixed>; D]),
+}
+
+#[derive(Debug, Clone)]
+pub struct RecurrenceConfig<F: PrimeField, const D: usize> {
+    advice: Column<Advice>,
+    selector: Selector,
+    instance: Column<Instance>,
+    coefficients: Coefficients<F, D>,
+}
+
+#[derive(Debug, Clone)]
+pub struct RecurrenceChip<F: PrimeField, const D: usize> {
+    config: RecurrenceConfig<F, D>,
+    coefficients: [F; D],
+}
+
+impl<F: PrimeField, const D: usize> RecurrenceChip<F, D> {
+    /// `coefficients` are the values the witness is computed with (and, in
+    /// [`Coefficients::Fixed`] mode, the values assigned to the fixed columns).
+    pub fn construct(config: RecurrenceConfig<F, D>, coefficients: [F; D]) -> Self {
+        if let Coefficients::Constant(constants) = &config.coefficients {
+            assert_eq!(
+                constants, &coefficients,
+                "coefficients differ from the gate's"
+            );
+        }
+        Self {
+            config,
+            coefficients,
+        }
+    }
+
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        advice: Column<Advice>,
+        instance: Column<Instance>,
+        coefficients: Coefficients<F, D>,
+    ) -> RecurrenceConfig<F, D> {
+        assert!(D > 0, "a recurrence needs at least one term");
+        let selector = meta.selector();
+
+        meta.enable_equality(advice);
+        meta.enable_equality(instance);
+
+        meta.create_gate("linear recurrence", |meta| {
+            let s = meta.query_selector(selector);
+            let next = meta.query_advice(advice, Rotation(D as i32));
+
+            // c_i multiplies a(n-i), which sits `D - i` rows above a(n)
+            let sum = (1..=D).fold(Expression::Constant(F::ZERO), |sum, i| {
+                let c = match &coefficients {
+                    Coefficients::Constant(c) => Expression::Constant(c[i - 1]),
+                    Coefficients::Fixed(c) => meta.query_fixed(c[i - 1], Rotation::cur()),
+                };
+                sum + c * meta.query_advice(advice, Rotation((D - i) as i32))
+            });
+
+            vec![s * (next - sum)]
+        });
+
+        RecurrenceConfig {
+            advice,
+            selector,
+            instance,
+            coefficients,
+        }
+    }
+
+    /// Copies the seeds `a(0)..a(D-1)` from instance rows `0..D` and computes the
+    /// sequence up to `a(n)`, returning its cell.
+    pub fn assign(
+        &self,
+        mut layouter: impl Layouter<F>,
+        n: usize,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        layouter.assign_region(
+            || "linear recurrence",
+            |mut region| {
+                let mut terms = (0..D)
+                    .map(|row| {
+                        region.assign_advice_from_instance(
+                            || format!("a({row})"),
+                            self.config.instance,
+                            row,
+                            self.config.advice,
+                            row,
+                        )
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                for row in D..=n {
+                    // the step starting at `row - D` computes a(row)
+                    let step = row - D;
+                    self.config.selector.enable(&mut region, step)?;
+                    if let Coefficients::Fixed(columns) = &self.config.coefficients {
+                        for (i, (column, c)) in columns.iter().zip(self.coefficients).enumerate() {
+                            region.assign_fixed(
+                                || format!("c_{}", i + 1),
+                                *column,
+                                step,
+                                || Value::known(c),
+                            )?;
+                        }
+                    }
+
+                    let value = (1..=D).fold(Value::known(F::ZERO), |sum, i| {
+                        let c = self.coefficients[i - 1];
+                        sum + terms[row - i].value().map(|a| c * a)
+                    });
+                    let cell = region.assign_advice(
+                        || format!("a({row})"),
+                        self.config.advice,
+                        row,
+                        || value,
+                    )?;
+                    terms.push(cell);
+                }
+
+                Ok(terms.swap_remove(n))
+            },
+        )
+    }
+
+    pub fn expose_public(
+        &self,
+        mut layouter: impl Layouter<F>,
+        cell: &AssignedCell<F, F>,
+        row: usize,
+    ) -> Result<(), Error> {
+        layouter.constrain_instance(cell.cell(), self.config.instance, row)
+    }
+}
+
+/// Computes `a(n)` natively for the given coefficients `[c_1, .., c_D]` and seeds
+/// `[a(0), .., a(D-1)]`.
+pub fn nth_term<F: Field, const D: usize>(coefficients: &[F; D], seeds: &[F; D], n: usize) -> F {
+    let mut terms = seeds.to_vec();
+    for row in D..=n {
+        let next = (1..=D).fold(F::ZERO, |sum, i| sum + coefficients[i - 1] * terms[row - i]);
+        terms.push(next);
+    }
+    terms[n]
+}
+
+/// A recurrence whose coefficients are known when the circuit is configured.
+pub trait Recurrence<F: PrimeField, const D: usize> {
+    fn coefficients() -> [F; D];
+}
+
+/// `a(n) = a(n-1) + a(n-2)`, also Lucas numbers with the seeds `[2, 1]`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Fibonacci;
+
+impl<F: PrimeField> Recurrence<F, 2> for Fibonacci {
+    fn coefficients() -> [F; 2] {
+        [F::ONE, F::ONE]
+    }
+}
+
+/// `a(n) = 2a(n-1) + a(n-2)`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Pell;
+
+impl<F: PrimeField> Recurrence<F, 2> for Pell {
+    fn coefficients() -> [F; 2] {
+        [F::from(2), F::ONE]
+    }
+}
+
+/// `a(n) = a(n-1) + a(n-2) + a(n-3)`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Tribonacci;
+
+impl<F: PrimeField> Recurrence<F, 3> for Tribonacci {
+    fn coefficients() -> [F; 3] {
+        [F::ONE, F::ONE, F::ONE]
+    }
+}
+
+/// Proves `a(n) = out` for the public instance `[a(0), .., a(D-1), out]`, with the
+/// coefficients of `R` as constants in the gate.
+#[derive(Debug, Clone, Default)]
+pub struct RecurrenceCircuit<F, R, const D: usize> {
+    pub n: usize,
+    _marker: PhantomData<(F, R)>,
+}
+
+impl<F, R, const D: usize> RecurrenceCircuit<F, R, D> {
+    pub fn new(n: usize) -> Self {
+        Self {
+            n,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<F: PrimeField, R: Recurrence<F, D>, const D: usize> Circuit<F> for RecurrenceCircuit<F, R, D> {
+    type Config = RecurrenceConfig<F, D>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::new(self.n)
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let advice = meta.advice_column();
+        let instance = meta.instance_column();
+        RecurrenceChip::configure(
+            meta,
+            advice,
+            instance,
+            Coefficients::Constant(R::coefficients()),
+        )
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = RecurrenceChip::construct(config, R::coefficients());
+        let out = chip.assign(layouter.namespace(|| "sequence"), self.n)?;
+        chip.expose_public(layouter.namespace(|| "out"), &out, D)
+    }
+}
+
+/// Same as [`RecurrenceCircuit`], but with user-defined coefficients assigned into
+/// fixed columns. Like `n`, the coefficients fix the circuit (and its verifying key).
+#[derive(Debug, Clone)]
+pub struct FixedRecurrenceCircuit<F: PrimeField, const D: usize> {
+    pub coefficients: [F; D],
+    pub n: usize,
+}
+
+impl<F: PrimeField, const D: usize> Circuit<F> for FixedRecurrenceCircuit<F, D> {
+    type Config = RecurrenceConfig<F, D>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        self.clone()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let advice = meta.advice_column();
+        let instance = meta.instance_column();
+        let columns = [(); D].map(|_| meta.fixed_column());
+        RecurrenceChip::configure(meta, advice, instance, Coefficients::Fixed(columns))
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = RecurrenceChip::construct(config, self.coefficients);
+        let out = chip.assign(layouter.namespace(|| "sequence"), self.n)?;
+        chip.expose_public(layouter.namespace(|| "out"), &out, D)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        proof::prove_and_verify,
+        rows::{min_k, mock_verify},
+    };
+    use halo2_proofs::pasta::Fp;
+
+    fn fp<const D: usize>(values: [u64; D]) -> [Fp; D] {
+        values.map(Fp::from)
+    }
+
+    fn instance<const D: usize>(seeds: [Fp; D], out: Fp) -> Vec<Vec<Fp>> {
+        let mut column = seeds.to_vec();
+        column.push(out);
+        vec![column]
+    }
+
+    fn check<C: Circuit<Fp>, const D: usize>(circuit: &C, seeds: [Fp; D], out: Fp) {
+        // the seeds are copied from the instance as inputs, so only the output is bumped
+        assert!(mock_verify(circuit, instance(seeds, out)));
+        assert!(!mock_verify(circuit, instance(seeds, out + Fp::one())));
+    }
+
+    #[test]
+    fn test_known_sequences() {
+        // F(20), L(20), P(20), T(20)
+        check(
+            &RecurrenceCircuit::<Fp, Fibonacci, 2>::new(20),
+            fp([0, 1]),
+            Fp::from(6765),
+        );
+        check(
+            &RecurrenceCircuit::<Fp, Fibonacci, 2>::new(20),
+            fp([2, 1]),
+            Fp::from(15127),
+        );
+        check(
+            &RecurrenceCircuit::<Fp, Pell, 2>::new(20),
+            fp([0, 1]),
+            Fp::from(15994428),
+        );
+        check(
+            &RecurrenceCircuit::<Fp, Tribonacci, 3>::new(20),
+            fp([0, 0, 1]),
+            Fp::from(35890),
+        );
+    }
+
+    #[test]
+    fn test_fixed_coefficients() {
+        // a(n) = 3a(n-1) - 3a(n-2) + a(n-3) walks the squares 0, 1, 4, 9, ...
+        let coefficients = [Fp::from(3), -Fp::from(3), Fp::one()];
+        for n in [0, 2, 3, 10, 25] {
+            let circuit = FixedRecurrenceCircuit { coefficients, n };
+            let out = nth_term(&coefficients, &fp([0, 1, 4]), n);
+            assert_eq!(out, Fp::from((n * n) as u64));
+            check(&circuit, fp([0, 1, 4]), out);
+        }
+
+        // the coefficients are part of the circuit: Fibonacci coefficients reject Pell numbers
+        let circuit = FixedRecurrenceCircuit {
+            coefficients: fp([1, 1]),
+            n: 20,
+        };
+        let pell = instance(fp([0, 1]), Fp::from(15994428));
+        assert!(!mock_verify(&circuit, pell));
+    }
+
+    #[test]
+    fn test_constant_and_fixed_agree() {
+        for n in [1, 2, 7, 30] {
+            let seeds = fp([3, 5]);
+            let out = nth_term(&<Pell as Recurrence<Fp, 2>>::coefficients(), &seeds, n);
+            check(&RecurrenceCircuit::<Fp, Pell, 2>::new(n), seeds, out);
+            check(
+                &FixedRecurrenceCircuit {
+                    coefficients: fp([2, 1]),
+                    n,
+                },
+                seeds,
+                out,
+            );
+        }
+    }
+
+    #[test]
+    fn test_linear_recurrence_real_prover() {
+        let circuit = RecurrenceCircuit::<Fp, Tribonacci, 3>::new(20);
+        let k = min_k(&circuit).unwrap();
+        let seeds = fp([0, 0, 1]);
+
+        prove_and_verify(k, circuit.clone(), &instance(seeds, Fp::from(35890))).unwrap();
+        assert!(prove_and_verify(k, circuit, &instance(seeds, Fp::from(35891))).is_err());
+    }
+
+    // $ cargo test --release --all-features plot_linear_recurrence
+    #[cfg(feature = "dev-graph")]
+    #[test]
+    fn plot_linear_recurrence() {
+        use plotters::prelude::*;
+        let root =
+            BitMapBackend::new("linear-recurrence-layout.png", (1024, 3096)).into_drawing_area();
+        root.fill(&WHITE).unwrap();
+        let root = root
+            .titled("Linear Recurrence Layout", ("sans-serif", 60))
+            .unwrap();
+
+        let circuit = FixedRecurrenceCircuit {
+            coefficients: fp([1, 1, 1]),
+            n: 10,
+        };
+        halo2_proofs::dev::CircuitLayout::default()
+            .render(min_k(&circuit).unwrap(), &circuit, &root)
+            .unwrap();
+    }
+}
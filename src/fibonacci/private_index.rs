@@ -0,0 +1,342 @@
+use std::marker::PhantomData;
+
+use ff::{Field, PrimeField};
+use halo2_proofs::{circuit::*, plonk::*, poly::Rotation};
+
+use crate::is_zero::{IsZeroChip, IsZeroConfig};
+
+// "I know n <= N_MAX with F(n) = out", without revealing n.
+//
+// The example2 table always runs for N_MAX + 1 rows, next to a countdown from n:
+//
+//   fib  | count | count_inv | done |   out    | q_first | q_acc | q_fib | q_last
+//   f(0) |  n    |    ...    |  z_0 | z_0·f(0) |    1    |   0   |   1   |   0
+//   f(1) |  n-1  |    ...    |  ... |   ...    |    0    |   1   |   1   |   0
+//   ...  |  ...  |    ...    |  ... |   ...    |    0    |   1   |  ...  |   0
+//   f(N) |  n-N  |    ...    |   1  |   f(n)   |    0    |   1   |   0   |   1
+//
+//   z_i    = is_zero(count_i)                 (IsZeroChip)
+//   done_i = done_{i-1} + z_i
+//   out_i  = out_{i-1} + z_i·f(i)
+//
+// The countdown hits zero at most once, at row n, so `out` latches f(n) there.
+// Requiring done = 1 on the last row rejects every n outside 0..=N_MAX.
+
+#[derive(Debug, Clone)]
+pub struct PrivateIndexConfig<F: PrimeField> {
+    fib: Column<Advice>,
+    count: Column<Advice>,
+    done: Column<Advice>,
+    out: Column<Advice>,
+    count_is_zero: IsZeroConfig<F>,
+    q_count: Selector,
+    q_first: Selector,
+    q_acc: Selector,
+    q_fib: Selector,
+    q_last: Selector,
+    instance: Column<Instance>,
+}
+
+#[derive(Debug, Clone)]
+pub struct PrivateIndexChip<F: PrimeField, const N_MAX: usize> {
+    config: PrivateIndexConfig<F>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField, const N_MAX: usize> PrivateIndexChip<F, N_MAX> {
+    pub fn construct(config: PrivateIndexConfig<F>) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        [fib, count, count_inv, done, out]: [Column<Advice>; 5],
+        instance: Column<Instance>,
+    ) -> PrivateIndexConfig<F> {
+        assert!(N_MAX >= 1, "the seeds take two rows");
+        let q_count = meta.selector();
+        let q_first = meta.selector();
+        let q_acc = meta.selector();
+        let q_fib = meta.selector();
+        let q_last = meta.selector();
+
+        meta.enable_equality(fib);
+        meta.enable_equality(out);
+        meta.enable_equality(instance);
+
+        // every row checks its countdown value. Simple selectors can only multiply an
+        // expression, so q_first + q_acc needs a selector of its own.
+        let count_is_zero = IsZeroChip::configure(
+            meta,
+            |meta| meta.query_selector(q_count),
+            |meta| meta.query_advice(count, Rotation::cur()),
+            count_inv,
+        );
+
+        // the example2 gate
+        meta.create_gate("add", |meta| {
+            let s = meta.query_selector(q_fib);
+            let a = meta.query_advice(fib, Rotation::cur());
+            let b = meta.query_advice(fib, Rotation::next());
+            let c = meta.query_advice(fib, Rotation(2));
+            vec![s * (a + b - c)]
+        });
+
+        meta.create_gate("latch first", |meta| {
+            let q = meta.query_selector(q_first);
+            let f = meta.query_advice(fib, Rotation::cur());
+            let done = meta.query_advice(done, Rotation::cur());
+            let out = meta.query_advice(out, Rotation::cur());
+            let z = count_is_zero.expr();
+
+            Constraints::with_selector(q, [("done", done - z.clone()), ("out", out - z * f)])
+        });
+
+        meta.create_gate("latch step", |meta| {
+            let q = meta.query_selector(q_acc);
+            let f = meta.query_advice(fib, Rotation::cur());
+            let count_prev = meta.query_advice(count, Rotation::prev());
+            let count = meta.query_advice(count, Rotation::cur());
+            let done_prev = meta.query_advice(done, Rotation::prev());
+            let done = meta.query_advice(done, Rotation::cur());
+            let out_prev = meta.query_advice(out, Rotation::prev());
+            let out = meta.query_advice(out, Rotation::cur());
+            let z = count_is_zero.expr();
+
+            Constraints::with_selector(
+                q,
+                [
+                    (
+                        "countdown",
+                        count_prev - count - Expression::Constant(F::ONE),
+                    ),
+                    ("done", done - done_prev - z.clone()),
+                    ("out", out - out_prev - z * f),
+                ],
+            )
+        });
+
+        meta.create_gate("n <= N_MAX", |meta| {
+            let q = meta.query_selector(q_last);
+            let done = meta.query_advice(done, Rotation::cur());
+            vec![q * (done - Expression::Constant(F::ONE))]
+        });
+
+        PrivateIndexConfig {
+            fib,
+            count,
+            done,
+            out,
+            count_is_zero,
+            q_count,
+            q_first,
+            q_acc,
+            q_fib,
+            q_last,
+            instance,
+        }
+    }
+
+    /// Runs the table for `N_MAX + 1` rows, seeded from instance rows 0 and 1, and
+    /// returns the cell latching `f(n)`.
+    pub fn assign(
+        &self,
+        mut layouter: impl Layouter<F>,
+        n: Value<u64>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let is_zero_chip = IsZeroChip::construct(self.config.count_is_zero.clone());
+
+        layouter.assign_region(
+            || "private index",
+            |mut region| {
+                let mut fib: Vec<AssignedCell<F, F>> = vec![];
+                let mut done = Value::known(F::ZERO);
+                let mut out = Value::known(F::ZERO);
+                let mut out_cell = None;
+
+                for row in 0..=N_MAX {
+                    let f = if row < 2 {
+                        region.assign_advice_from_instance(
+                            || format!("f({row})"),
+                            self.config.instance,
+                            row,
+                            self.config.fib,
+                            row,
+                        )?
+                    } else {
+                        let value = fib[row - 2].value().copied() + fib[row - 1].value();
+                        region.assign_advice(
+                            || format!("f({row})"),
+                            self.config.fib,
+                            row,
+                            || value,
+                        )?
+                    };
+
+                    self.config.q_count.enable(&mut region, row)?;
+                    if row == 0 {
+                        self.config.q_first.enable(&mut region, row)?;
+                    } else {
+                        self.config.q_acc.enable(&mut region, row)?;
+                    }
+                    if row + 2 <= N_MAX {
+                        self.config.q_fib.enable(&mut region, row)?;
+                    }
+                    if row == N_MAX {
+                        self.config.q_last.enable(&mut region, row)?;
+                    }
+
+                    let count = n.map(|n| F::from(n) - F::from(row as u64));
+                    region.assign_advice(|| "count", self.config.count, row, || count)?;
+                    is_zero_chip.assign(&mut region, row, count)?;
+
+                    let z = count.map(|count| if count == F::ZERO { F::ONE } else { F::ZERO });
+                    done = done + z;
+                    out = out + z * f.value();
+                    region.assign_advice(|| "done", self.config.done, row, || done)?;
+                    out_cell =
+                        Some(region.assign_advice(|| "out", self.config.out, row, || out)?);
+
+                    fib.push(f);
+                }
+
+                Ok(out_cell.expect("N_MAX + 1 rows were assigned"))
+            },
+        )
+    }
+
+    pub fn expose_public(
+        &self,
+        mut layouter: impl Layouter<F>,
+        cell: &AssignedCell<F, F>,
+        row: usize,
+    ) -> Result<(), Error> {
+        layouter.constrain_instance(cell.cell(), self.config.instance, row)
+    }
+}
+
+/// Proves `f(n) = out` for the public instance `[f(0), f(1), out]` and a private
+/// `n <= N_MAX`. The circuit always uses `N_MAX + 1` rows, so one verifying key
+/// serves every `n`.
+#[derive(Debug, Clone, Default)]
+pub struct PrivateIndexCircuit<F, const N_MAX: usize> {
+    pub n: Value<u64>,
+    _marker: PhantomData<F>,
+}
+
+impl<F, const N_MAX: usize> PrivateIndexCircuit<F, N_MAX> {
+    pub fn new(n: Value<u64>) -> Self {
+        Self {
+            n,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<F: PrimeField, const N_MAX: usize> Circuit<F> for PrivateIndexCircuit<F, N_MAX> {
+    type Config = PrivateIndexConfig<F>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let advice = [(); 5].map(|_| meta.advice_column());
+        let instance = meta.instance_column();
+        PrivateIndexChip::<F, N_MAX>::configure(meta, advice, instance)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        let chip = PrivateIndexChip::<F, N_MAX>::construct(config);
+        let out = chip.assign(layouter.namespace(|| "table"), self.n)?;
+        chip.expose_public(layouter.namespace(|| "out"), &out, 2)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        fibonacci::example1::nth_term,
+        proof::{prove, setup, verify},
+        rows::{min_k, mock_verify},
+    };
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    const N_MAX: usize = 10;
+
+    fn public_input(n: usize) -> Vec<Fp> {
+        vec![Fp::one(), Fp::one(), nth_term(Fp::one(), Fp::one(), n)]
+    }
+
+    #[test]
+    fn test_private_index() {
+        for n in 0..=N_MAX {
+            let circuit = PrivateIndexCircuit::<Fp, N_MAX>::new(Value::known(n as u64));
+            assert!(mock_verify(&circuit, vec![public_input(n)]), "n = {n}");
+
+            // the claimed output must be f(n)
+            let mut wrong = public_input(n);
+            wrong[2] += Fp::one();
+            assert!(
+                !mock_verify(&circuit, vec![wrong]),
+                "n = {n} accepted a wrong output"
+            );
+        }
+    }
+
+    #[test]
+    fn test_private_index_out_of_range() {
+        let k = min_k(&PrivateIndexCircuit::<Fp, N_MAX>::default()).unwrap();
+
+        // the countdown never hits zero, so `done` and `out` stay 0
+        for n in [N_MAX as u64 + 1, u64::MAX] {
+            let circuit = PrivateIndexCircuit::<Fp, N_MAX>::new(Value::known(n));
+            let latched = vec![Fp::one(), Fp::one(), Fp::zero()];
+            let prover = MockProver::run(k, &circuit, vec![latched]).unwrap();
+            assert!(prover.verify().is_err(), "n = {n} was accepted");
+
+            let prover = MockProver::run(k, &circuit, vec![public_input(N_MAX + 1)]).unwrap();
+            assert!(prover.verify().is_err(), "n = {n} was accepted");
+        }
+    }
+
+    #[test]
+    fn test_private_index_real_prover() {
+        // one set of keys, generated without n, verifies proofs for different n
+        let empty = PrivateIndexCircuit::<Fp, N_MAX>::default();
+        let (params, pk) = setup(min_k(&empty).unwrap(), &empty).unwrap();
+
+        for n in [0, 3, N_MAX] {
+            let circuit = PrivateIndexCircuit::<Fp, N_MAX>::new(Value::known(n as u64));
+            let instances = [public_input(n)];
+            let proof = prove(&params, &pk, circuit, &instances).unwrap();
+            verify(&params, pk.get_vk(), &instances, &proof).unwrap();
+        }
+    }
+
+    // $ cargo test --release --all-features plot_private_index
+    #[cfg(feature = "dev-graph")]
+    #[test]
+    fn plot_private_index() {
+        use plotters::prelude::*;
+        let root = BitMapBackend::new("private-index-layout.png", (1024, 3096)).into_drawing_area();
+        root.fill(&WHITE).unwrap();
+        let root = root
+            .titled("Private Index Layout", ("sans-serif", 60))
+            .unwrap();
+
+        let circuit = PrivateIndexCircuit::<Fp, N_MAX>::default();
+        halo2_proofs::dev::CircuitLayout::default()
+            .render(min_k(&circuit).unwrap(), &circuit, &root)
+            .unwrap();
+    }
+}
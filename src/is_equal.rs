@@ -0,0 +1,502 @@
+use std::marker::PhantomData;
+
+use ff::{Field, PrimeField};
+use halo2_proofs::{circuit::*, plonk::*, poly::Rotation};
+
+use crate::is_zero::{IsZeroChip, IsZeroConfig};
+
+// Equality gadgets on top of the IsZeroChip inverse trick:
+//
+//   is_equal(lhs, rhs)          = is_zero(lhs - rhs)
+//   is_equal_constant(value, c) = is_zero(value - c)
+//   assert_not_equal(lhs, rhs):   (lhs - rhs) · diff_inv = 1, i.e. lhs - rhs is invertible
+//   assert_equal(lhs, rhs):       lhs - rhs = 0, no witness needed
+//
+// All assign paths compute the inverse witness themselves, like `IsZeroChip::assign`.
+
+#[derive(Clone, Debug)]
+pub struct IsEqualConfig<F> {
+    is_zero: IsZeroConfig<F>,
+    // set by `configure_cells`: the selector and the columns `compare` copies into
+    cells: Option<(Selector, Column<Advice>, Column<Advice>)>,
+}
+
+impl<F: PrimeField> IsEqualConfig<F> {
+    /// 1 if `lhs == rhs`, 0 otherwise, usable in gates on the same row.
+    pub fn expr(&self) -> Expression<F> {
+        self.is_zero.expr()
+    }
+}
+
+pub struct IsEqualChip<F: PrimeField> {
+    config: IsEqualConfig<F>,
+}
+
+impl<F: PrimeField> IsEqualChip<F> {
+    pub fn construct(config: IsEqualConfig<F>) -> Self {
+        IsEqualChip { config }
+    }
+
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        q_enable: impl FnOnce(&mut VirtualCells<'_, F>) -> Expression<F>,
+        lhs: impl FnOnce(&mut VirtualCells<'_, F>) -> Expression<F>,
+        rhs: impl FnOnce(&mut VirtualCells<'_, F>) -> Expression<F>,
+        diff_inv: Column<Advice>,
+    ) -> IsEqualConfig<F> {
+        let is_zero = IsZeroChip::configure(meta, q_enable, |meta| lhs(meta) - rhs(meta), diff_inv);
+        IsEqualConfig {
+            is_zero,
+            cells: None,
+        }
+    }
+
+    /// Same as `configure`, with the 0/1 result assigned into `output`.
+    pub fn configure_with_output(
+        meta: &mut ConstraintSystem<F>,
+        q_enable: impl FnOnce(&mut VirtualCells<'_, F>) -> Expression<F>,
+        lhs: impl FnOnce(&mut VirtualCells<'_, F>) -> Expression<F>,
+        rhs: impl FnOnce(&mut VirtualCells<'_, F>) -> Expression<F>,
+        diff_inv: Column<Advice>,
+        output: Column<Advice>,
+    ) -> IsEqualConfig<F> {
+        let is_zero = IsZeroChip::configure_with_output(
+            meta,
+            q_enable,
+            |meta| lhs(meta) - rhs(meta),
+            diff_inv,
+            output,
+        );
+        IsEqualConfig {
+            is_zero,
+            cells: None,
+        }
+    }
+
+    /// Configures the chip to compare already assigned cells with [`IsEqualChip::compare`].
+    pub fn configure_cells(
+        meta: &mut ConstraintSystem<F>,
+        [lhs, rhs, diff_inv, output]: [Column<Advice>; 4],
+    ) -> IsEqualConfig<F> {
+        let q = meta.selector();
+        meta.enable_equality(lhs);
+        meta.enable_equality(rhs);
+
+        let mut config = Self::configure_with_output(
+            meta,
+            |meta| meta.query_selector(q),
+            |meta| meta.query_advice(lhs, Rotation::cur()),
+            |meta| meta.query_advice(rhs, Rotation::cur()),
+            diff_inv,
+            output,
+        );
+        config.cells = Some((q, lhs, rhs));
+        config
+    }
+
+    /// Assigns the inverse of `lhs - rhs` (and the result, in output mode).
+    pub fn assign(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        lhs: Value<F>,
+        rhs: Value<F>,
+    ) -> Result<Option<AssignedCell<F, F>>, Error> {
+        IsZeroChip::construct(self.config.is_zero.clone()).assign(region, offset, lhs - rhs)
+    }
+
+    /// Copies `lhs` and `rhs` into a new row and returns the cell holding `lhs == rhs`.
+    pub fn compare(
+        &self,
+        mut layouter: impl Layouter<F>,
+        lhs: &AssignedCell<F, F>,
+        rhs: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let (q, lhs_column, rhs_column) = self
+            .config
+            .cells
+            .expect("IsEqualChip::compare needs `configure_cells`");
+
+        layouter.assign_region(
+            || "is equal",
+            |mut region| {
+                q.enable(&mut region, 0)?;
+                let lhs = lhs.copy_advice(|| "lhs", &mut region, lhs_column, 0)?;
+                let rhs = rhs.copy_advice(|| "rhs", &mut region, rhs_column, 0)?;
+
+                let output =
+                    self.assign(&mut region, 0, lhs.value().copied(), rhs.value().copied())?;
+                Ok(output.expect("`configure_cells` sets an output column"))
+            },
+        )
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct IsEqualConstantConfig<F> {
+    is_equal: IsEqualConfig<F>,
+    constant: F,
+}
+
+impl<F: PrimeField> IsEqualConstantConfig<F> {
+    /// 1 if `value == constant`, 0 otherwise.
+    pub fn expr(&self) -> Expression<F> {
+        self.is_equal.expr()
+    }
+}
+
+/// Compares an expression with a constant fixed at configure time.
+pub struct IsEqualConstantChip<F: PrimeField> {
+    config: IsEqualConstantConfig<F>,
+}
+
+impl<F: PrimeField> IsEqualConstantChip<F> {
+    pub fn construct(config: IsEqualConstantConfig<F>) -> Self {
+        IsEqualConstantChip { config }
+    }
+
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        q_enable: impl FnOnce(&mut VirtualCells<'_, F>) -> Expression<F>,
+        value: impl FnOnce(&mut VirtualCells<'_, F>) -> Expression<F>,
+        constant: F,
+        diff_inv: Column<Advice>,
+    ) -> IsEqualConstantConfig<F> {
+        let is_equal = IsEqualChip::configure(
+            meta,
+            q_enable,
+            value,
+            |_| Expression::Constant(constant),
+            diff_inv,
+        );
+        IsEqualConstantConfig { is_equal, constant }
+    }
+
+    /// Same as `configure`, with the 0/1 result assigned into `output`.
+    pub fn configure_with_output(
+        meta: &mut ConstraintSystem<F>,
+        q_enable: impl FnOnce(&mut VirtualCells<'_, F>) -> Expression<F>,
+        value: impl FnOnce(&mut VirtualCells<'_, F>) -> Expression<F>,
+        constant: F,
+        diff_inv: Column<Advice>,
+        output: Column<Advice>,
+    ) -> IsEqualConstantConfig<F> {
+        let is_equal = IsEqualChip::configure_with_output(
+            meta,
+            q_enable,
+            value,
+            |_| Expression::Constant(constant),
+            diff_inv,
+            output,
+        );
+        IsEqualConstantConfig { is_equal, constant }
+    }
+
+    pub fn assign(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        value: Value<F>,
+    ) -> Result<Option<AssignedCell<F, F>>, Error> {
+        IsEqualChip::construct(self.config.is_equal.clone()).assign(
+            region,
+            offset,
+            value,
+            Value::known(self.config.constant),
+        )
+    }
+}
+
+/// The relation an [`AssertChip`] enforces between two expressions.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Assertion {
+    Equal,
+    NotEqual,
+}
+
+#[derive(Clone, Debug)]
+pub struct AssertConfig {
+    assertion: Assertion,
+    // only `NotEqual` needs the inverse witness
+    diff_inv: Option<Column<Advice>>,
+}
+
+pub struct AssertChip<F: PrimeField> {
+    config: AssertConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField> AssertChip<F> {
+    pub fn construct(config: AssertConfig) -> Self {
+        AssertChip {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Enforces `lhs == rhs` wherever `q_enable` is set.
+    pub fn configure_equal(
+        meta: &mut ConstraintSystem<F>,
+        q_enable: impl FnOnce(&mut VirtualCells<'_, F>) -> Expression<F>,
+        lhs: impl FnOnce(&mut VirtualCells<'_, F>) -> Expression<F>,
+        rhs: impl FnOnce(&mut VirtualCells<'_, F>) -> Expression<F>,
+    ) -> AssertConfig {
+        meta.create_gate("assert equal", |meta| {
+            let diff = lhs(meta) - rhs(meta);
+            let q_enable = q_enable(meta);
+            vec![q_enable * diff]
+        });
+
+        AssertConfig {
+            assertion: Assertion::Equal,
+            diff_inv: None,
+        }
+    }
+
+    /// Enforces `lhs != rhs` wherever `q_enable` is set, by witnessing `1 / (lhs - rhs)`.
+    pub fn configure_not_equal(
+        meta: &mut ConstraintSystem<F>,
+        q_enable: impl FnOnce(&mut VirtualCells<'_, F>) -> Expression<F>,
+        lhs: impl FnOnce(&mut VirtualCells<'_, F>) -> Expression<F>,
+        rhs: impl FnOnce(&mut VirtualCells<'_, F>) -> Expression<F>,
+        diff_inv: Column<Advice>,
+    ) -> AssertConfig {
+        meta.create_gate("assert not equal", |meta| {
+            let diff = lhs(meta) - rhs(meta);
+            let q_enable = q_enable(meta);
+            let diff_inv = meta.query_advice(diff_inv, Rotation::cur());
+            vec![q_enable * (Expression::Constant(F::ONE) - diff * diff_inv)]
+        });
+
+        AssertConfig {
+            assertion: Assertion::NotEqual,
+            diff_inv: Some(diff_inv),
+        }
+    }
+
+    pub fn assertion(&self) -> Assertion {
+        self.config.assertion
+    }
+
+    /// Assigns the witness the assertion needs, if any.
+    pub fn assign(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        lhs: Value<F>,
+        rhs: Value<F>,
+    ) -> Result<(), Error> {
+        if let Some(diff_inv) = self.config.diff_inv {
+            // a zero difference has no inverse, the gate then fails on 1 - 0 = 1
+            let inv = (lhs - rhs).map(|diff| diff.invert().unwrap_or(F::ZERO));
+            region.assign_advice(|| "diff inv", diff_inv, offset, || inv)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rows::{assert_bits_bound, mock_verify};
+    use halo2_proofs::pasta::Fp;
+
+    const SEVEN: u64 = 7;
+
+    #[derive(Clone, Debug)]
+    struct TestConfig<F: PrimeField> {
+        q: Selector,
+        a: Column<Advice>,
+        b: Column<Advice>,
+        is_equal: IsEqualConfig<F>,
+        is_seven: IsEqualConstantConfig<F>,
+        cells: IsEqualConfig<F>,
+        instance: Column<Instance>,
+    }
+
+    // instance: [a == b (expressions), a == 7, a == b (cells)]
+    #[derive(Default)]
+    struct IsEqualCircuit<F> {
+        a: Value<F>,
+        b: Value<F>,
+    }
+
+    impl<F: PrimeField> Circuit<F> for IsEqualCircuit<F> {
+        type Config = TestConfig<F>;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let q = meta.selector();
+            let [a, b, diff_inv, output, const_inv, const_output] =
+                [(); 6].map(|_| meta.advice_column());
+            let instance = meta.instance_column();
+            for column in [a, b, output, const_output] {
+                meta.enable_equality(column);
+            }
+            meta.enable_equality(instance);
+
+            let is_equal = IsEqualChip::configure_with_output(
+                meta,
+                |meta| meta.query_selector(q),
+                |meta| meta.query_advice(a, Rotation::cur()),
+                |meta| meta.query_advice(b, Rotation::cur()),
+                diff_inv,
+                output,
+            );
+            let is_seven = IsEqualConstantChip::configure_with_output(
+                meta,
+                |meta| meta.query_selector(q),
+                |meta| meta.query_advice(a, Rotation::cur()),
+                F::from(SEVEN),
+                const_inv,
+                const_output,
+            );
+            let cell_columns = [(); 4].map(|_| meta.advice_column());
+            let cells = IsEqualChip::configure_cells(meta, cell_columns);
+
+            TestConfig {
+                q,
+                a,
+                b,
+                is_equal,
+                is_seven,
+                cells,
+                instance,
+            }
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let is_equal = IsEqualChip::construct(config.is_equal);
+            let is_seven = IsEqualConstantChip::construct(config.is_seven);
+
+            let (a, b, outputs) = layouter.assign_region(
+                || "a, b",
+                |mut region| {
+                    config.q.enable(&mut region, 0)?;
+                    let a = region.assign_advice(|| "a", config.a, 0, || self.a)?;
+                    let b = region.assign_advice(|| "b", config.b, 0, || self.b)?;
+                    let equal = is_equal.assign(&mut region, 0, self.a, self.b)?;
+                    let seven = is_seven.assign(&mut region, 0, self.a)?;
+                    Ok((a, b, [equal.unwrap(), seven.unwrap()]))
+                },
+            )?;
+            let compared = IsEqualChip::construct(config.cells).compare(
+                layouter.namespace(|| "cells"),
+                &a,
+                &b,
+            )?;
+
+            for (row, cell) in outputs.iter().chain([&compared]).enumerate() {
+                layouter.constrain_instance(cell.cell(), config.instance, row)?;
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_is_equal() {
+        let bit = |b: bool| if b { Fp::one() } else { Fp::zero() };
+
+        for (a, b) in [(3, 3), (3, 4), (7, 7), (7, 0), (0, 0)] {
+            let circuit = IsEqualCircuit {
+                a: Value::known(Fp::from(a)),
+                b: Value::known(Fp::from(b)),
+            };
+            let expected = vec![bit(a == b), bit(a == SEVEN), bit(a == b)];
+            assert_bits_bound(&circuit, &expected);
+        }
+    }
+
+    // enables either the `Equal` or the `NotEqual` assertion on (a, b)
+    struct AssertCircuit<F> {
+        a: Value<F>,
+        b: Value<F>,
+        assertion: Assertion,
+    }
+
+    impl<F: PrimeField> Circuit<F> for AssertCircuit<F> {
+        type Config = (
+            Column<Advice>,
+            Column<Advice>,
+            [(Selector, AssertConfig); 2],
+        );
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                a: Value::unknown(),
+                b: Value::unknown(),
+                assertion: self.assertion,
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let [a, b, diff_inv] = [(); 3].map(|_| meta.advice_column());
+            let [q_equal, q_not_equal] = [(); 2].map(|_| meta.selector());
+
+            let equal = AssertChip::configure_equal(
+                meta,
+                |meta| meta.query_selector(q_equal),
+                |meta| meta.query_advice(a, Rotation::cur()),
+                |meta| meta.query_advice(b, Rotation::cur()),
+            );
+            let not_equal = AssertChip::configure_not_equal(
+                meta,
+                |meta| meta.query_selector(q_not_equal),
+                |meta| meta.query_advice(a, Rotation::cur()),
+                |meta| meta.query_advice(b, Rotation::cur()),
+                diff_inv,
+            );
+            (a, b, [(q_equal, equal), (q_not_equal, not_equal)])
+        }
+
+        fn synthesize(
+            &self,
+            (a, b, assertions): Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let (q, config) = assertions
+                .into_iter()
+                .find(|(_, config)| config.assertion == self.assertion)
+                .unwrap();
+            let chip = AssertChip::construct(config);
+
+            layouter.assign_region(
+                || "assert",
+                |mut region| {
+                    q.enable(&mut region, 0)?;
+                    region.assign_advice(|| "a", a, 0, || self.a)?;
+                    region.assign_advice(|| "b", b, 0, || self.b)?;
+                    chip.assign(&mut region, 0, self.a, self.b)
+                },
+            )
+        }
+    }
+
+    #[test]
+    fn test_assert() {
+        for (a, b) in [(3, 3), (3, 4), (0, 5), (0, 0)] {
+            for assertion in [Assertion::Equal, Assertion::NotEqual] {
+                let circuit = AssertCircuit {
+                    a: Value::known(Fp::from(a)),
+                    b: Value::known(Fp::from(b)),
+                    assertion,
+                };
+                let holds = (a == b) == (assertion == Assertion::Equal);
+                assert_eq!(
+                    mock_verify(&circuit, vec![]),
+                    holds,
+                    "{a} {assertion:?} {b}"
+                );
+            }
+        }
+    }
+}
@@ -5,6 +5,8 @@ use halo2_proofs::{circuit::*, plonk::*, poly::Rotation};
 pub struct IsZeroConfig<F> {
     pub value_inv: Column<Advice>,
     pub is_zero_expr: Expression<F>,
+    // when set, holds `is_zero_expr` as an assigned 0/1 cell in the same row
+    pub output: Option<Column<Advice>>,
 }
 
 impl<F: PrimeField> IsZeroConfig<F> {
@@ -27,6 +29,29 @@ impl<F: PrimeField> IsZeroChip<F> {
         q_enable: impl FnOnce(&mut VirtualCells<'_, F>) -> Expression<F>,
         value: impl FnOnce(&mut VirtualCells<'_, F>) -> Expression<F>,
         value_inv: Column<Advice>,
+    ) -> IsZeroConfig<F> {
+        Self::configure_inner(meta, q_enable, value, value_inv, None)
+    }
+
+    /// Same as `configure`, and additionally constrains `output` to `1 - value * value_inv`,
+    /// so `assign` returns the result as a cell that can be copied or exposed.
+    pub fn configure_with_output(
+        meta: &mut ConstraintSystem<F>,
+        q_enable: impl FnOnce(&mut VirtualCells<'_, F>) -> Expression<F>,
+        value: impl FnOnce(&mut VirtualCells<'_, F>) -> Expression<F>,
+        value_inv: Column<Advice>,
+        output: Column<Advice>,
+    ) -> IsZeroConfig<F> {
+        meta.enable_equality(output);
+        Self::configure_inner(meta, q_enable, value, value_inv, Some(output))
+    }
+
+    fn configure_inner(
+        meta: &mut ConstraintSystem<F>,
+        q_enable: impl FnOnce(&mut VirtualCells<'_, F>) -> Expression<F>,
+        value: impl FnOnce(&mut VirtualCells<'_, F>) -> Expression<F>,
+        value_inv: Column<Advice>,
+        output: Option<Column<Advice>>,
     ) -> IsZeroConfig<F> {
         let mut is_zero_expr = Expression::Constant(F::ZERO);
 
@@ -46,21 +71,31 @@ impl<F: PrimeField> IsZeroChip<F> {
 
             // create a constant —— use Expression::Constant
             is_zero_expr = Expression::Constant(F::ONE) - value.clone() * value_inv;
-            vec![q_enable * value * is_zero_expr.clone()]  // gate's constraints
+            let mut constraints = vec![q_enable.clone() * value * is_zero_expr.clone()];  // gate's constraints
+
+            // output == 1 - value * value_inv, i.e. 1 if value == 0 else 0
+            if let Some(output) = output {
+                let output = meta.query_advice(output, Rotation::cur());
+                constraints.push(q_enable * (output - is_zero_expr.clone()));
+            }
+            constraints
         });
 
         IsZeroConfig {
             value_inv,
             is_zero_expr,
+            output,
         }
     }
 
+    /// Assigns the inverse witness for `value`, plus the 0/1 result in output mode,
+    /// whose cell is returned.
     pub fn assign(
         &self,
         region: &mut Region<'_, F>,
         offset: usize,
         value: Value<F>,
-    ) -> Result<(), Error> {
+    ) -> Result<Option<AssignedCell<F, F>>, Error> {
         // value.invert()  OR  F::ZERO
         let value_inv = value.map(|value| value.invert().unwrap_or(F::ZERO));
         region.assign_advice(|| "value inv", 
@@ -68,6 +103,96 @@ impl<F: PrimeField> IsZeroChip<F> {
           offset, 
           || value_inv
         )?;
-        Ok(())
+
+        self.config
+            .output
+            .map(|output| {
+                let is_zero = value.map(|value| if value == F::ZERO { F::ONE } else { F::ZERO });
+                region.assign_advice(|| "is zero", output, offset, || is_zero)
+            })
+            .transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rows::min_k;
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    // exposes `value == 0` as the public output
+    #[derive(Default)]
+    struct IsZeroCircuit<F> {
+        value: Value<F>,
+    }
+
+    impl<F: PrimeField> Circuit<F> for IsZeroCircuit<F> {
+        type Config = (Selector, Column<Advice>, IsZeroConfig<F>, Column<Instance>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let q = meta.selector();
+            let value = meta.advice_column();
+            let value_inv = meta.advice_column();
+            let output = meta.advice_column();
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+
+            let is_zero = IsZeroChip::configure_with_output(
+                meta,
+                |meta| meta.query_selector(q),
+                |meta| meta.query_advice(value, Rotation::cur()),
+                value_inv,
+                output,
+            );
+            (q, value, is_zero, instance)
+        }
+
+        fn synthesize(
+            &self,
+            (q, value, is_zero, instance): Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let chip = IsZeroChip::construct(is_zero);
+            let output = layouter.assign_region(
+                || "is zero",
+                |mut region| {
+                    q.enable(&mut region, 0)?;
+                    region.assign_advice(|| "value", value, 0, || self.value)?;
+                    chip.assign(&mut region, 0, self.value)
+                },
+            )?;
+            let output = output.expect("configured with an output column");
+            layouter.constrain_instance(output.cell(), instance, 0)
+        }
+    }
+
+    #[test]
+    fn test_is_zero_output() {
+        let k = min_k(&IsZeroCircuit::<Fp>::default()).unwrap();
+        for (value, is_zero) in [(0, true), (1, false), (5, false)] {
+            let circuit = IsZeroCircuit {
+                value: Value::known(Fp::from(value)),
+            };
+            let expected = if is_zero { Fp::one() } else { Fp::zero() };
+
+            let prover = MockProver::run(k, &circuit, vec![vec![expected]]).unwrap();
+            prover.assert_satisfied();
+
+            let wrong = Fp::one() - expected;
+            let prover = MockProver::run(k, &circuit, vec![vec![wrong]]).unwrap();
+            assert!(prover.verify().is_err(), "value = {value}");
+        }
+
+        // -1 is not zero either
+        let circuit = IsZeroCircuit {
+            value: Value::known(-Fp::one()),
+        };
+        let prover = MockProver::run(k, &circuit, vec![vec![Fp::zero()]]).unwrap();
+        prover.assert_satisfied();
     }
 }
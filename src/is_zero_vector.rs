@@ -0,0 +1,406 @@
+use ff::{Field, PrimeField};
+use halo2_proofs::{circuit::*, plonk::*, poly::Rotation};
+
+use crate::is_zero::{IsZeroChip, IsZeroConfig};
+
+// Zero tests over N values with a single inverse witness.
+//
+// Any zero, the product trick: a field has no zero divisors, so prod = Π v_i is zero
+// iff some v_i is, and one `is_zero(prod)` answers for all N values:
+//
+//   v_0 ... v_{N-1} | prod | prod_inv
+//
+//   prod     = Π v_i
+//   any_zero = is_zero(prod)                  (IsZeroChip)
+//
+// All zero: the sum of squares Σ v_i² is *not* sound here. For p ≡ 1 (mod 4), which holds
+// for both Pasta fields, -1 has a square root i and (1, i) squares to 1 + i² = 0. Instead
+// the prover points at one of the values, the pivot, and inverts it:
+//
+//   v_0 ... v_{N-1} | pivot | pivot_inv
+//
+//   all_zero        = 1 - pivot · pivot_inv
+//   Π (pivot - v_i) = 0                       pivot is one of the values
+//   all_zero · v_i  = 0                       for every i
+//
+// all_zero = 0 needs an invertible pivot, i.e. a non-zero value. Any other all_zero forces
+// every v_i, hence the pivot, to 0, and then all_zero = 1. Both gates have degree N + 1.
+
+fn collect<F: Field, const N: usize>(values: &[Value<F>; N]) -> Value<Vec<F>> {
+    values.iter().fold(Value::known(vec![]), |acc, value| {
+        acc.zip(*value).map(|(mut acc, value)| {
+            acc.push(value);
+            acc
+        })
+    })
+}
+
+#[derive(Clone, Debug)]
+pub struct AnyZeroConfig<F> {
+    prod: Column<Advice>,
+    is_zero: IsZeroConfig<F>,
+}
+
+impl<F: PrimeField> AnyZeroConfig<F> {
+    /// 1 if at least one value is zero, 0 otherwise.
+    pub fn expr(&self) -> Expression<F> {
+        self.is_zero.expr()
+    }
+}
+
+pub struct AnyZeroChip<F: PrimeField, const N: usize> {
+    config: AnyZeroConfig<F>,
+}
+
+impl<F: PrimeField, const N: usize> AnyZeroChip<F, N> {
+    pub fn construct(config: AnyZeroConfig<F>) -> Self {
+        Self { config }
+    }
+
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        q_enable: impl Fn(&mut VirtualCells<'_, F>) -> Expression<F>,
+        values: impl FnOnce(&mut VirtualCells<'_, F>) -> [Expression<F>; N],
+        [prod, prod_inv]: [Column<Advice>; 2],
+    ) -> AnyZeroConfig<F> {
+        Self::configure_product(meta, &q_enable, values, prod);
+        let is_zero = IsZeroChip::configure(
+            meta,
+            q_enable,
+            |meta| meta.query_advice(prod, Rotation::cur()),
+            prod_inv,
+        );
+        AnyZeroConfig { prod, is_zero }
+    }
+
+    /// Same as `configure`, with the 0/1 result assigned into `output`.
+    pub fn configure_with_output(
+        meta: &mut ConstraintSystem<F>,
+        q_enable: impl Fn(&mut VirtualCells<'_, F>) -> Expression<F>,
+        values: impl FnOnce(&mut VirtualCells<'_, F>) -> [Expression<F>; N],
+        [prod, prod_inv]: [Column<Advice>; 2],
+        output: Column<Advice>,
+    ) -> AnyZeroConfig<F> {
+        Self::configure_product(meta, &q_enable, values, prod);
+        let is_zero = IsZeroChip::configure_with_output(
+            meta,
+            q_enable,
+            |meta| meta.query_advice(prod, Rotation::cur()),
+            prod_inv,
+            output,
+        );
+        AnyZeroConfig { prod, is_zero }
+    }
+
+    fn configure_product(
+        meta: &mut ConstraintSystem<F>,
+        q_enable: &impl Fn(&mut VirtualCells<'_, F>) -> Expression<F>,
+        values: impl FnOnce(&mut VirtualCells<'_, F>) -> [Expression<F>; N],
+        prod: Column<Advice>,
+    ) {
+        assert!(N > 0, "no values to test");
+        meta.create_gate("any zero product", |meta| {
+            let values = values(meta);
+            let q_enable = q_enable(meta);
+            let prod = meta.query_advice(prod, Rotation::cur());
+
+            let product = values.into_iter().reduce(|acc, value| acc * value).unwrap();
+            vec![q_enable * (prod - product)]
+        });
+    }
+
+    /// Assigns the product and its inverse witness (and the result, in output mode).
+    pub fn assign(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        values: [Value<F>; N],
+    ) -> Result<Option<AssignedCell<F, F>>, Error> {
+        let prod = collect(&values).map(|values| values.into_iter().product::<F>());
+        region.assign_advice(|| "prod", self.config.prod, offset, || prod)?;
+        IsZeroChip::construct(self.config.is_zero.clone()).assign(region, offset, prod)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct AllZeroConfig<F> {
+    pivot: Column<Advice>,
+    pivot_inv: Column<Advice>,
+    all_zero_expr: Expression<F>,
+    output: Option<Column<Advice>>,
+}
+
+impl<F: PrimeField> AllZeroConfig<F> {
+    /// 1 if every value is zero, 0 otherwise.
+    pub fn expr(&self) -> Expression<F> {
+        self.all_zero_expr.clone()
+    }
+}
+
+pub struct AllZeroChip<F: PrimeField, const N: usize> {
+    config: AllZeroConfig<F>,
+}
+
+impl<F: PrimeField, const N: usize> AllZeroChip<F, N> {
+    pub fn construct(config: AllZeroConfig<F>) -> Self {
+        Self { config }
+    }
+
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        q_enable: impl FnOnce(&mut VirtualCells<'_, F>) -> Expression<F>,
+        values: impl FnOnce(&mut VirtualCells<'_, F>) -> [Expression<F>; N],
+        pivot: [Column<Advice>; 2],
+    ) -> AllZeroConfig<F> {
+        Self::configure_inner(meta, q_enable, values, pivot, None)
+    }
+
+    /// Same as `configure`, with the 0/1 result assigned into `output`.
+    pub fn configure_with_output(
+        meta: &mut ConstraintSystem<F>,
+        q_enable: impl FnOnce(&mut VirtualCells<'_, F>) -> Expression<F>,
+        values: impl FnOnce(&mut VirtualCells<'_, F>) -> [Expression<F>; N],
+        pivot: [Column<Advice>; 2],
+        output: Column<Advice>,
+    ) -> AllZeroConfig<F> {
+        meta.enable_equality(output);
+        Self::configure_inner(meta, q_enable, values, pivot, Some(output))
+    }
+
+    fn configure_inner(
+        meta: &mut ConstraintSystem<F>,
+        q_enable: impl FnOnce(&mut VirtualCells<'_, F>) -> Expression<F>,
+        values: impl FnOnce(&mut VirtualCells<'_, F>) -> [Expression<F>; N],
+        [pivot, pivot_inv]: [Column<Advice>; 2],
+        output: Option<Column<Advice>>,
+    ) -> AllZeroConfig<F> {
+        assert!(N > 0, "no values to test");
+        let mut all_zero_expr = Expression::Constant(F::ZERO);
+
+        meta.create_gate("all zero", |meta| {
+            let values = values(meta);
+            let q_enable = q_enable(meta);
+            let pivot = meta.query_advice(pivot, Rotation::cur());
+            let pivot_inv = meta.query_advice(pivot_inv, Rotation::cur());
+
+            all_zero_expr = Expression::Constant(F::ONE) - pivot.clone() * pivot_inv;
+            let pivot_is_a_value = values
+                .iter()
+                .map(|value| pivot.clone() - value.clone())
+                .reduce(|acc, factor| acc * factor)
+                .unwrap();
+
+            let mut constraints = vec![pivot_is_a_value];
+            constraints.extend(
+                values
+                    .into_iter()
+                    .map(|value| all_zero_expr.clone() * value),
+            );
+            if let Some(output) = output {
+                let output = meta.query_advice(output, Rotation::cur());
+                constraints.push(output - all_zero_expr.clone());
+            }
+            Constraints::with_selector(q_enable, constraints)
+        });
+
+        AllZeroConfig {
+            pivot,
+            pivot_inv,
+            all_zero_expr,
+            output,
+        }
+    }
+
+    /// Picks the first non-zero value as the pivot, if there is one, and assigns it with
+    /// its inverse (and the result, in output mode).
+    pub fn assign(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        values: [Value<F>; N],
+    ) -> Result<Option<AssignedCell<F, F>>, Error> {
+        let pivot = collect(&values).map(|values| {
+            values
+                .into_iter()
+                .find(|value| *value != F::ZERO)
+                .unwrap_or(F::ZERO)
+        });
+        let pivot_inv = pivot.map(|pivot| pivot.invert().unwrap_or(F::ZERO));
+        region.assign_advice(|| "pivot", self.config.pivot, offset, || pivot)?;
+        region.assign_advice(|| "pivot inv", self.config.pivot_inv, offset, || pivot_inv)?;
+
+        self.config
+            .output
+            .map(|output| {
+                let all_zero = pivot.map(|pivot| if pivot == F::ZERO { F::ONE } else { F::ZERO });
+                region.assign_advice(|| "all zero", output, offset, || all_zero)
+            })
+            .transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rows::{assert_bits_bound, min_k};
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    const N: usize = 3;
+
+    // instance: [all zero, any zero]. `forged` replaces the honest (pivot, pivot_inv).
+    #[derive(Default)]
+    struct ZeroCircuit<F> {
+        values: [Value<F>; N],
+        forged: Option<(F, F)>,
+    }
+
+    impl<F: PrimeField> Circuit<F> for ZeroCircuit<F> {
+        type Config = (
+            Selector,
+            [Column<Advice>; N],
+            AllZeroConfig<F>,
+            AnyZeroConfig<F>,
+            Column<Instance>,
+        );
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let q = meta.selector();
+            let values = [(); N].map(|_| meta.advice_column());
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+
+            let [pivot, pivot_inv, all_zero_out, prod, prod_inv, any_zero_out] =
+                [(); 6].map(|_| meta.advice_column());
+
+            let all_zero = AllZeroChip::configure_with_output(
+                meta,
+                |meta| meta.query_selector(q),
+                |meta| values.map(|value| meta.query_advice(value, Rotation::cur())),
+                [pivot, pivot_inv],
+                all_zero_out,
+            );
+            let any_zero = AnyZeroChip::configure_with_output(
+                meta,
+                |meta| meta.query_selector(q),
+                |meta| values.map(|value| meta.query_advice(value, Rotation::cur())),
+                [prod, prod_inv],
+                any_zero_out,
+            );
+            (q, values, all_zero, any_zero, instance)
+        }
+
+        fn synthesize(
+            &self,
+            (q, columns, all_zero, any_zero, instance): Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let all_zero_chip = AllZeroChip::<F, N>::construct(all_zero.clone());
+            let any_zero_chip = AnyZeroChip::<F, N>::construct(any_zero);
+
+            let outputs = layouter.assign_region(
+                || "values",
+                |mut region| {
+                    q.enable(&mut region, 0)?;
+                    for (column, value) in columns.iter().zip(self.values) {
+                        region.assign_advice(|| "value", *column, 0, || value)?;
+                    }
+
+                    let all = match self.forged {
+                        None => all_zero_chip.assign(&mut region, 0, self.values)?,
+                        Some((pivot, pivot_inv)) => {
+                            region.assign_advice(
+                                || "pivot",
+                                all_zero.pivot,
+                                0,
+                                || Value::known(pivot),
+                            )?;
+                            region.assign_advice(
+                                || "pivot inv",
+                                all_zero.pivot_inv,
+                                0,
+                                || Value::known(pivot_inv),
+                            )?;
+                            let all_zero_value = F::ONE - pivot * pivot_inv;
+                            Some(region.assign_advice(
+                                || "all zero",
+                                all_zero.output.unwrap(),
+                                0,
+                                || Value::known(all_zero_value),
+                            )?)
+                        }
+                    };
+                    let any = any_zero_chip.assign(&mut region, 0, self.values)?;
+                    Ok([all.unwrap(), any.unwrap()])
+                },
+            )?;
+
+            for (row, cell) in outputs.iter().enumerate() {
+                layouter.constrain_instance(cell.cell(), instance, row)?;
+            }
+            Ok(())
+        }
+    }
+
+    fn circuit(values: [Fp; N]) -> ZeroCircuit<Fp> {
+        ZeroCircuit {
+            values: values.map(Value::known),
+            forged: None,
+        }
+    }
+
+    fn sqrt_minus_one() -> Fp {
+        let i = Option::<Fp>::from((-Fp::one()).sqrt()).expect("p = 1 mod 4");
+        // the counterexample to an all-zero test by sum of squares
+        assert_eq!(Fp::one() + i.square(), Fp::zero());
+        i
+    }
+
+    #[test]
+    fn test_all_zero_any_zero() {
+        let [zero, one, two, three] = [0, 1, 2, 3].map(Fp::from);
+        let i = sqrt_minus_one();
+
+        for (values, all_zero, any_zero) in [
+            ([zero, zero, zero], true, true),
+            ([zero, three, zero], false, true),
+            ([three, zero, zero], false, true),
+            ([zero, zero, -one], false, true),
+            ([one, two, three], false, false),
+            ([one, i, zero], false, true),
+            ([one, i, two], false, false),
+            ([i, -i, one], false, false),
+        ] {
+            let instance = vec![Fp::from(all_zero as u64), Fp::from(any_zero as u64)];
+            assert_bits_bound(&circuit(values), &instance);
+        }
+    }
+
+    #[test]
+    fn test_all_zero_forged_pivot() {
+        let k = min_k(&ZeroCircuit::<Fp>::default()).unwrap();
+        let [zero, two, three] = [0, 2, 3].map(Fp::from);
+
+        for (values, (pivot, pivot_inv), any_zero) in [
+            // "not all zero" for zeros needs a pivot that is not one of them
+            ([zero, zero, zero], (Fp::one(), Fp::one()), true),
+            // "all zero" with a zero pivot next to a non-zero value
+            ([zero, three, zero], (zero, zero), true),
+            // a pivot with a wrong inverse gives all_zero = 1 - 6, not a bit
+            ([zero, three, zero], (three, two), true),
+        ] {
+            let circuit = ZeroCircuit {
+                values: values.map(Value::known),
+                forged: Some((pivot, pivot_inv)),
+            };
+            let all_zero = Fp::one() - pivot * pivot_inv;
+            let instance = vec![all_zero, Fp::from(any_zero as u64)];
+            let prover = MockProver::run(k, &circuit, vec![instance]).unwrap();
+            assert!(prover.verify().is_err(), "{values:?} with pivot {pivot:?}");
+        }
+    }
+}
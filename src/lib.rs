@@ -1,3 +1,15 @@
-mod fibonacci;
-mod is_zero;
-mod range_check;
\ No newline at end of file
+pub mod artifact;
+pub mod boolean;
+pub mod circuits;
+pub mod comparator;
+pub mod division;
+pub mod extrema;
+pub mod fibonacci;
+pub mod is_equal;
+pub mod is_zero;
+pub mod is_zero_vector;
+pub mod piecewise;
+pub mod proof;
+pub mod range_check;
+pub mod rows;
+pub mod select;
@@ -0,0 +1,293 @@
+use std::{env, fs::File, io::BufWriter, path::PathBuf, process};
+
+use halo2_examples::{
+    artifact::{verify_artifact, ProofArtifact},
+    circuits::{CircuitId, CircuitVisitor},
+    proof::MAX_K,
+};
+use halo2_proofs::{dev::MockProver, pasta::Fp, plonk::Circuit};
+
+const USAGE: &str = "\
+usage: halo2-examples <command> [options]
+
+commands:
+    list                                         list the example circuits
+    mock <circuit> [--k K] [--inputs x,y,..]     run the circuit through the MockProver
+    prove <circuit> [--k K] [--inputs x,y,..] --out <file>
+                                                 create and save a proof artifact
+    verify <file>                                verify a saved proof artifact
+    layout <circuit> [--k K] [--png <file>]      render the circuit layout (needs the
+                                                 `dev-graph` feature)
+
+<circuit> is one of the names printed by `list`, e.g. `fibonacci1:20` or `range-check2`.
+Without `--inputs` the circuit's default inputs are used.";
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if let Err(e) = run(&args) {
+        eprintln!("error: {e}");
+        process::exit(1);
+    }
+}
+
+fn run(args: &[String]) -> Result<(), String> {
+    let (command, args) = args.split_first().ok_or(USAGE)?;
+    match command.as_str() {
+        "list" => {
+            list();
+            Ok(())
+        }
+        "mock" => mock(&Options::parse(args)?),
+        "prove" => prove(&Options::parse(args)?),
+        "verify" => verify(args),
+        "layout" => layout(&Options::parse(args)?),
+        "help" | "-h" | "--help" => {
+            println!("{USAGE}");
+            Ok(())
+        }
+        other => Err(format!("unknown command `{other}`\n\n{USAGE}")),
+    }
+}
+
+struct Options {
+    circuit: CircuitId,
+    k: Option<u32>,
+    inputs: Vec<u64>,
+    out: Option<PathBuf>,
+    // only `layout` reads it, which needs dev-graph; still parsed without it, so
+    // `layout --png` gets to say so
+    #[cfg_attr(not(feature = "dev-graph"), allow(dead_code))]
+    png: Option<PathBuf>,
+}
+
+impl Options {
+    fn parse(args: &[String]) -> Result<Self, String> {
+        let mut args = args.iter();
+        let circuit = args
+            .next()
+            .ok_or("missing <circuit>")?
+            .parse::<CircuitId>()
+            .map_err(|e| e.to_string())?;
+
+        let mut options = Options {
+            circuit,
+            k: None,
+            inputs: vec![],
+            out: None,
+            png: None,
+        };
+        while let Some(flag) = args.next() {
+            let value = args
+                .next()
+                .ok_or_else(|| format!("missing value for `{flag}`"))?;
+            match flag.as_str() {
+                "--k" => {
+                    let k = value.parse().map_err(|_| format!("invalid k `{value}`"))?;
+                    options.k = Some(check_k(k)?);
+                }
+                "--inputs" => {
+                    options.inputs = value
+                        .split(',')
+                        .map(|x| x.trim().parse().map_err(|_| format!("invalid input `{x}`")))
+                        .collect::<Result<_, _>>()?;
+                }
+                "--out" => options.out = Some(value.into()),
+                "--png" => options.png = Some(value.into()),
+                other => return Err(format!("unknown option `{other}`")),
+            }
+        }
+        Ok(options)
+    }
+
+    fn k(&self) -> Result<u32, String> {
+        match self.k {
+            Some(k) => Ok(k),
+            None => check_k(self.circuit.min_k().map_err(|e| e.to_string())?),
+        }
+    }
+}
+
+fn check_k(k: u32) -> Result<u32, String> {
+    if k > MAX_K {
+        return Err(format!("k = {k} is above the supported maximum of {MAX_K}"));
+    }
+    Ok(k)
+}
+
+fn list() {
+    for id in CircuitId::examples() {
+        println!(
+            "{:<24} k = {:<3} inputs: {:<28} {}",
+            id.to_string(),
+            id.min_k().map_or("?".to_string(), |k| k.to_string()),
+            id.input_names().join(","),
+            id.description()
+        );
+    }
+}
+
+struct Mock(u32);
+
+impl CircuitVisitor for Mock {
+    type Output = Result<(), String>;
+
+    fn visit<C: Circuit<Fp>>(self, circuit: C, instances: Vec<Vec<Fp>>) -> Self::Output {
+        let prover = MockProver::run(self.0, &circuit, instances).map_err(|e| e.to_string())?;
+        prover.verify().map_err(|failures| {
+            failures
+                .iter()
+                .map(|failure| failure.to_string())
+                .collect::<Vec<_>>()
+                .join("\n")
+        })
+    }
+}
+
+fn mock(options: &Options) -> Result<(), String> {
+    let k = options.k()?;
+    options
+        .circuit
+        .with_circuit(&options.inputs, Mock(k))
+        .map_err(|e| e.to_string())??;
+    println!("`{}` is satisfied (k = {k})", options.circuit);
+    Ok(())
+}
+
+fn prove(options: &Options) -> Result<(), String> {
+    let out = options.out.as_ref().ok_or("`prove` needs `--out <file>`")?;
+    let artifact = ProofArtifact::create(options.circuit, options.k()?, &options.inputs)
+        .map_err(|e| e.to_string())?;
+
+    let file = File::create(out).map_err(|e| format!("cannot create {}: {e}", out.display()))?;
+    artifact
+        .write(BufWriter::new(file))
+        .map_err(|e| format!("cannot write {}: {e}", out.display()))?;
+    println!(
+        "wrote a {}-byte proof for `{}` (k = {}) to {}",
+        artifact.proof.len(),
+        artifact.circuit,
+        artifact.k,
+        out.display()
+    );
+    Ok(())
+}
+
+fn verify(args: &[String]) -> Result<(), String> {
+    let path = match args {
+        [path] => path,
+        _ => return Err(format!("`verify` expects exactly one <file>\n\n{USAGE}")),
+    };
+    let file = File::open(path).map_err(|e| format!("cannot open {path}: {e}"))?;
+    let artifact = ProofArtifact::read(file).map_err(|e| e.to_string())?;
+    verify_artifact(&artifact).map_err(|e| e.to_string())?;
+    println!(
+        "valid proof for `{}` (k = {}) with instances {:?}",
+        artifact.circuit, artifact.k, artifact.instances
+    );
+    Ok(())
+}
+
+#[cfg(feature = "dev-graph")]
+fn layout(options: &Options) -> Result<(), String> {
+    let path = options.png.clone().unwrap_or_else(|| {
+        format!("{}-layout.png", options.circuit)
+            .replace(':', "-")
+            .into()
+    });
+    let title = format!("{} Layout", options.circuit);
+    options
+        .circuit
+        .with_circuit(
+            &options.inputs,
+            Layout {
+                k: options.k()?,
+                path: &path,
+                title,
+            },
+        )
+        .map_err(|e| e.to_string())??;
+    println!("wrote {}", path.display());
+    Ok(())
+}
+
+#[cfg(not(feature = "dev-graph"))]
+fn layout(_: &Options) -> Result<(), String> {
+    Err(
+        "`layout` needs the `dev-graph` feature: cargo run --features dev-graph -- layout ..."
+            .into(),
+    )
+}
+
+#[cfg(feature = "dev-graph")]
+struct Layout<'a> {
+    k: u32,
+    path: &'a std::path::Path,
+    title: String,
+}
+
+#[cfg(feature = "dev-graph")]
+impl CircuitVisitor for Layout<'_> {
+    type Output = Result<(), String>;
+
+    fn visit<C: Circuit<Fp>>(self, circuit: C, _: Vec<Vec<Fp>>) -> Self::Output {
+        use plotters::prelude::*;
+
+        let root = BitMapBackend::new(self.path, (1024, 3096)).into_drawing_area();
+        root.fill(&WHITE).map_err(|e| e.to_string())?;
+        let root = root
+            .titled(&self.title, ("sans-serif", 60))
+            .map_err(|e| e.to_string())?;
+
+        halo2_proofs::dev::CircuitLayout::default()
+            .render(self.k, &circuit, &root)
+            .map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &str) -> Vec<String> {
+        args.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn test_parse_options() {
+        let options =
+            Options::parse(&args("fibonacci1:20 --k 6 --inputs 1,2 --out a.bin")).unwrap();
+        assert_eq!(
+            options.circuit,
+            "fibonacci1:20".parse::<CircuitId>().unwrap()
+        );
+        assert_eq!(options.k, Some(6));
+        assert_eq!(options.inputs, vec![1, 2]);
+        assert_eq!(options.out, Some("a.bin".into()));
+
+        let options = Options::parse(&args("fibonacci1:20 --png out.png")).unwrap();
+        assert_eq!(options.png, Some("out.png".into()));
+        assert_eq!(options.k().unwrap(), options.circuit.min_k().unwrap());
+    }
+
+    #[test]
+    fn test_parse_rejects_bad_options() {
+        let too_large = format!("fibonacci1:20 --k {}", MAX_K + 1);
+        for bad in [
+            "",
+            "fibonacci1:20 --k",
+            "fibonacci1:20 --k x",
+            too_large.as_str(),
+            "fibonacci1:20 --inputs 1,x",
+            "fibonacci1:20 --svg out.svg",
+        ] {
+            assert!(Options::parse(&args(bad)).is_err(), "`{bad}`");
+        }
+    }
+
+    #[cfg(not(feature = "dev-graph"))]
+    #[test]
+    fn test_layout_needs_dev_graph() {
+        let err = run(&args("layout fibonacci1:20 --png out.png")).unwrap_err();
+        assert!(err.contains("dev-graph"), "{err}");
+    }
+}
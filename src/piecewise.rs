@@ -0,0 +1,451 @@
+use std::ops::{Add, Mul, Neg, Sub};
+
+use ff::PrimeField;
+use halo2_proofs::{circuit::*, plonk::*, poly::Rotation};
+
+use crate::is_zero::{IsZeroChip, IsZeroConfig};
+
+// Branchy functions of N inputs, declared instead of hand-written:
+//
+//   let [a, b, c] = Expr::vars();
+//   let f = Piecewise::when(Condition::equal(a.clone(), b.clone()), c).otherwise(a - b);
+//   let config = PiecewiseChip::configure(meta, f, [col_a, col_b, col_c], col_out);
+//
+// Every condition gets an inverse column and an IsZeroChip, z_k = is_zero(condition_k).
+// Branches are tried in order, the first one whose condition holds is taken:
+//
+//   out = z_0 · out_0
+//       + (1 - z_0) · z_1 · out_1
+//       + ...
+//       + (1 - z_0) · ... · (1 - z_{B-1}) · otherwise
+//
+// The z_k are exact bits (see is_zero.rs), so exactly one term survives. Each branch
+// adds 2 to the degree of the output gate, keep the number of branches small.
+
+/// An expression over the inputs of a piecewise function.
+#[derive(Clone, Debug)]
+pub enum Expr<F> {
+    Var(usize),
+    Constant(F),
+    Negated(Box<Expr<F>>),
+    Sum(Box<Expr<F>>, Box<Expr<F>>),
+    Product(Box<Expr<F>>, Box<Expr<F>>),
+}
+
+impl<F: PrimeField> Expr<F> {
+    /// The inputs `0..N`, in order.
+    pub fn vars<const N: usize>() -> [Self; N] {
+        std::array::from_fn(Expr::Var)
+    }
+
+    pub fn constant(value: F) -> Self {
+        Expr::Constant(value)
+    }
+
+    // the gate side, `vars` being the queried input cells
+    fn to_expression(&self, vars: &[Expression<F>]) -> Expression<F> {
+        match self {
+            Expr::Var(i) => vars[*i].clone(),
+            Expr::Constant(value) => Expression::Constant(*value),
+            Expr::Negated(e) => -e.to_expression(vars),
+            Expr::Sum(a, b) => a.to_expression(vars) + b.to_expression(vars),
+            Expr::Product(a, b) => a.to_expression(vars) * b.to_expression(vars),
+        }
+    }
+
+    // the witness side
+    fn evaluate(&self, vars: &[Value<F>]) -> Value<F> {
+        match self {
+            Expr::Var(i) => vars[*i],
+            Expr::Constant(value) => Value::known(*value),
+            Expr::Negated(e) => -e.evaluate(vars),
+            Expr::Sum(a, b) => a.evaluate(vars) + b.evaluate(vars),
+            Expr::Product(a, b) => a.evaluate(vars) * b.evaluate(vars),
+        }
+    }
+
+    fn max_var(&self) -> Option<usize> {
+        match self {
+            Expr::Var(i) => Some(*i),
+            Expr::Constant(_) => None,
+            Expr::Negated(e) => e.max_var(),
+            Expr::Sum(a, b) | Expr::Product(a, b) => a.max_var().max(b.max_var()),
+        }
+    }
+}
+
+impl<F> Neg for Expr<F> {
+    type Output = Expr<F>;
+    fn neg(self) -> Expr<F> {
+        Expr::Negated(Box::new(self))
+    }
+}
+
+impl<F> Add for Expr<F> {
+    type Output = Expr<F>;
+    fn add(self, rhs: Expr<F>) -> Expr<F> {
+        Expr::Sum(Box::new(self), Box::new(rhs))
+    }
+}
+
+impl<F> Sub for Expr<F> {
+    type Output = Expr<F>;
+    fn sub(self, rhs: Expr<F>) -> Expr<F> {
+        Expr::Sum(Box::new(self), Box::new(-rhs))
+    }
+}
+
+impl<F> Mul for Expr<F> {
+    type Output = Expr<F>;
+    fn mul(self, rhs: Expr<F>) -> Expr<F> {
+        Expr::Product(Box::new(self), Box::new(rhs))
+    }
+}
+
+/// A branch condition, tested with an `IsZeroChip`.
+#[derive(Clone, Debug)]
+pub enum Condition<F> {
+    IsZero(Expr<F>),
+    IsEqual(Expr<F>, Expr<F>),
+}
+
+impl<F: PrimeField> Condition<F> {
+    pub fn is_zero(value: Expr<F>) -> Self {
+        Condition::IsZero(value)
+    }
+
+    pub fn equal(lhs: Expr<F>, rhs: Expr<F>) -> Self {
+        Condition::IsEqual(lhs, rhs)
+    }
+
+    // the condition holds iff this is zero
+    fn value(&self) -> Expr<F> {
+        match self {
+            Condition::IsZero(value) => value.clone(),
+            Condition::IsEqual(lhs, rhs) => lhs.clone() - rhs.clone(),
+        }
+    }
+}
+
+/// Branches under construction, finished by [`PiecewiseBranches::otherwise`].
+#[derive(Clone, Debug)]
+pub struct PiecewiseBranches<F> {
+    branches: Vec<(Condition<F>, Expr<F>)>,
+}
+
+impl<F: PrimeField> PiecewiseBranches<F> {
+    pub fn when(mut self, condition: Condition<F>, output: Expr<F>) -> Self {
+        self.branches.push((condition, output));
+        self
+    }
+
+    /// The output when no condition holds.
+    pub fn otherwise(self, output: Expr<F>) -> Piecewise<F> {
+        Piecewise {
+            branches: self.branches,
+            otherwise: output,
+        }
+    }
+}
+
+/// A function declared as ordered `(condition, output)` branches plus a default.
+#[derive(Clone, Debug)]
+pub struct Piecewise<F> {
+    branches: Vec<(Condition<F>, Expr<F>)>,
+    otherwise: Expr<F>,
+}
+
+impl<F: PrimeField> Piecewise<F> {
+    pub fn when(condition: Condition<F>, output: Expr<F>) -> PiecewiseBranches<F> {
+        PiecewiseBranches {
+            branches: vec![(condition, output)],
+        }
+    }
+
+    // the exprs the gate needs: the tested values, the branch outputs and the default
+    fn exprs(&self) -> impl Iterator<Item = Expr<F>> + '_ {
+        self.branches
+            .iter()
+            .flat_map(|(condition, output)| [condition.value(), output.clone()])
+            .chain([self.otherwise.clone()])
+    }
+}
+
+// sums the branch outputs weighted by "taken", for the gate and the witness alike
+fn select<T>(zs: Vec<T>, outputs: Vec<T>, otherwise: T, one: T, zero: T) -> T
+where
+    T: Clone + Add<Output = T> + Sub<Output = T> + Mul<Output = T>,
+{
+    let mut remaining = one.clone();
+    let mut out = zero;
+    for (z, output) in zs.into_iter().zip(outputs) {
+        out = out + remaining.clone() * z.clone() * output;
+        remaining = remaining * (one.clone() - z);
+    }
+    out + remaining * otherwise
+}
+
+fn query<F: PrimeField, const N: usize>(
+    meta: &mut VirtualCells<'_, F>,
+    inputs: [Column<Advice>; N],
+) -> [Expression<F>; N] {
+    inputs.map(|column| meta.query_advice(column, Rotation::cur()))
+}
+
+/// An input of [`PiecewiseChip::assign`]: a fresh witness, or a cell copied in from
+/// another region (e.g. the output of another chip).
+#[derive(Clone, Debug)]
+pub enum Input<F: PrimeField> {
+    Witness(Value<F>),
+    Cell(AssignedCell<F, F>),
+}
+
+impl<F: PrimeField> From<Value<F>> for Input<F> {
+    fn from(value: Value<F>) -> Self {
+        Input::Witness(value)
+    }
+}
+
+impl<F: PrimeField> From<AssignedCell<F, F>> for Input<F> {
+    fn from(cell: AssignedCell<F, F>) -> Self {
+        Input::Cell(cell)
+    }
+}
+
+impl<F: PrimeField> From<&AssignedCell<F, F>> for Input<F> {
+    fn from(cell: &AssignedCell<F, F>) -> Self {
+        Input::Cell(cell.clone())
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct PiecewiseConfig<F: PrimeField, const N: usize> {
+    selector: Selector,
+    inputs: [Column<Advice>; N],
+    output: Column<Advice>,
+    conditions: Vec<IsZeroConfig<F>>,
+    function: Piecewise<F>,
+}
+
+pub struct PiecewiseChip<F: PrimeField, const N: usize> {
+    config: PiecewiseConfig<F, N>,
+}
+
+impl<F: PrimeField, const N: usize> PiecewiseChip<F, N> {
+    pub fn construct(config: PiecewiseConfig<F, N>) -> Self {
+        Self { config }
+    }
+
+    /// Allocates one inverse column per condition and the gates computing `function`
+    /// of `inputs` into `output`.
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        function: Piecewise<F>,
+        inputs: [Column<Advice>; N],
+        output: Column<Advice>,
+    ) -> PiecewiseConfig<F, N> {
+        assert!(
+            function.exprs().all(|e| e.max_var() < Some(N)),
+            "the function reads more than {N} inputs"
+        );
+        let selector = meta.selector();
+        for column in inputs.into_iter().chain([output]) {
+            meta.enable_equality(column);
+        }
+
+        let conditions: Vec<_> = function
+            .branches
+            .iter()
+            .map(|(condition, _)| {
+                let inverse = meta.advice_column();
+                IsZeroChip::configure(
+                    meta,
+                    |meta| meta.query_selector(selector),
+                    |meta| condition.value().to_expression(&query(meta, inputs)),
+                    inverse,
+                )
+            })
+            .collect();
+
+        meta.create_gate("piecewise", |meta| {
+            let q = meta.query_selector(selector);
+            let vars = query(meta, inputs);
+            let out = meta.query_advice(output, Rotation::cur());
+
+            let zs = conditions.iter().map(|c| c.expr()).collect();
+            let outputs = function
+                .branches
+                .iter()
+                .map(|(_, output)| output.to_expression(&vars))
+                .collect();
+            let selected = select(
+                zs,
+                outputs,
+                function.otherwise.to_expression(&vars),
+                Expression::Constant(F::ONE),
+                Expression::Constant(F::ZERO),
+            );
+            vec![q * (out - selected)]
+        });
+
+        PiecewiseConfig {
+            selector,
+            inputs,
+            output,
+            conditions,
+            function,
+        }
+    }
+
+    /// Assigns (or copies) the inputs, the inverse witnesses and the output, whose cell
+    /// is returned.
+    pub fn assign(
+        &self,
+        mut layouter: impl Layouter<F>,
+        inputs: [Input<F>; N],
+    ) -> Result<AssignedCell<F, F>, Error> {
+        layouter.assign_region(
+            || "piecewise",
+            |mut region| {
+                let mut values = [Value::unknown(); N];
+                for (i, (&column, input)) in self.config.inputs.iter().zip(&inputs).enumerate() {
+                    let cell = match input {
+                        Input::Witness(value) => {
+                            region.assign_advice(|| format!("input {i}"), column, 0, || *value)?
+                        }
+                        Input::Cell(cell) => {
+                            cell.copy_advice(|| format!("input {i}"), &mut region, column, 0)?
+                        }
+                    };
+                    values[i] = cell.value().copied();
+                }
+                self.assign_output(&mut region, &values)
+            },
+        )
+    }
+
+    fn assign_output(
+        &self,
+        region: &mut Region<'_, F>,
+        inputs: &[Value<F>; N],
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let config = &self.config;
+        let function = &config.function;
+        config.selector.enable(region, 0)?;
+
+        let mut zs = vec![];
+        for (is_zero, (condition, _)) in config.conditions.iter().zip(&function.branches) {
+            let value = condition.value().evaluate(inputs);
+            IsZeroChip::construct(is_zero.clone()).assign(region, 0, value)?;
+            zs.push(value.map(|v| if v == F::ZERO { F::ONE } else { F::ZERO }));
+        }
+        let outputs = function
+            .branches
+            .iter()
+            .map(|(_, output)| output.evaluate(inputs))
+            .collect();
+        let out = select(
+            zs,
+            outputs,
+            function.otherwise.evaluate(inputs),
+            Value::known(F::ONE),
+            Value::known(F::ZERO),
+        );
+        region.assign_advice(|| "output", config.output, 0, || out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rows::min_k;
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    //   x == y      => 0
+    //   x - y == 1  => 1
+    //   x == 0      => y · y
+    //   otherwise   => x + 7
+    fn function() -> Piecewise<Fp> {
+        let [x, y] = Expr::vars();
+        Piecewise::when(
+            Condition::equal(x.clone(), y.clone()),
+            Expr::constant(Fp::zero()),
+        )
+        .when(
+            Condition::is_zero(x.clone() - y.clone() - Expr::constant(Fp::one())),
+            Expr::constant(Fp::one()),
+        )
+        .when(Condition::is_zero(x.clone()), y.clone() * y)
+        .otherwise(x + Expr::constant(Fp::from(7)))
+    }
+
+    fn expected(x: u64, y: u64) -> u64 {
+        if x == y {
+            0
+        } else if x == y + 1 {
+            1
+        } else if x == 0 {
+            y * y
+        } else {
+            x + 7
+        }
+    }
+
+    #[derive(Default)]
+    struct PiecewiseCircuit {
+        x: Value<Fp>,
+        y: Value<Fp>,
+    }
+
+    impl Circuit<Fp> for PiecewiseCircuit {
+        type Config = (PiecewiseConfig<Fp, 2>, Column<Instance>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let inputs = [(); 2].map(|_| meta.advice_column());
+            let output = meta.advice_column();
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+            (
+                PiecewiseChip::configure(meta, function(), inputs, output),
+                instance,
+            )
+        }
+
+        fn synthesize(
+            &self,
+            (config, instance): Self::Config,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            let chip = PiecewiseChip::construct(config);
+            let out = chip.assign(
+                layouter.namespace(|| "f"),
+                [self.x, self.y].map(Input::from),
+            )?;
+            layouter.constrain_instance(out.cell(), instance, 0)
+        }
+    }
+
+    #[test]
+    fn test_piecewise() {
+        let k = min_k(&PiecewiseCircuit::default()).unwrap();
+
+        // every branch, including overlapping conditions (x = y = 0 takes the first)
+        for (x, y) in [(3, 3), (0, 0), (4, 3), (1, 0), (0, 5), (9, 2), (2, 9)] {
+            let circuit = PiecewiseCircuit {
+                x: Value::known(Fp::from(x)),
+                y: Value::known(Fp::from(y)),
+            };
+            let out = Fp::from(expected(x, y));
+            let prover = MockProver::run(k, &circuit, vec![vec![out]]).unwrap();
+            prover.assert_satisfied();
+
+            let prover = MockProver::run(k, &circuit, vec![vec![out + Fp::one()]]).unwrap();
+            assert!(prover.verify().is_err(), "f({x}, {y})");
+        }
+    }
+}
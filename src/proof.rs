@@ -0,0 +1,88 @@
+use halo2_proofs::{
+    pasta::{EqAffine, Fp},
+    plonk::{
+        create_proof, keygen_pk, keygen_vk, verify_proof, Circuit, Error, ProvingKey,
+        SingleVerifier, VerifyingKey,
+    },
+    poly::commitment::Params,
+    transcript::{Blake2bRead, Blake2bWrite, Challenge255},
+};
+use rand_core::OsRng;
+
+// Real (non-mock) proving over the Pasta cycle: IPA commitments on Vesta (`EqAffine`),
+// whose scalar field is the `Fp` every example circuit is written over.
+//
+//   Params::new(k) -> keygen_vk -> keygen_pk -> create_proof -> verify_proof
+//
+// Keys are always generated from `circuit.without_witnesses()`, exactly as a verifier
+// that never sees the witness would do it.
+
+/// The largest `k` the pipeline sets up parameters for. `Params::new` allocates 2^k
+/// points and panics from k = 32 on, so a `k` read from a file or the command line is
+/// checked against this first.
+pub const MAX_K: u32 = 20;
+
+/// Generates the IPA parameters and the proving key for the shape of `circuit`.
+pub fn setup<C: Circuit<Fp>>(
+    k: u32,
+    circuit: &C,
+) -> Result<(Params<EqAffine>, ProvingKey<EqAffine>), Error> {
+    let params = Params::new(k);
+    let pk = keygen(&params, circuit)?;
+    Ok((params, pk))
+}
+
+/// Generates the proving key (and with it the verifying key) for `circuit` under `params`.
+pub fn keygen<C: Circuit<Fp>>(
+    params: &Params<EqAffine>,
+    circuit: &C,
+) -> Result<ProvingKey<EqAffine>, Error> {
+    let vk = keygen_vk(params, &circuit.without_witnesses())?;
+    keygen_pk(params, vk, &circuit.without_witnesses())
+}
+
+/// Creates a proof with a Blake2b transcript. `instances` holds one vector per instance column.
+pub fn prove<C: Circuit<Fp>>(
+    params: &Params<EqAffine>,
+    pk: &ProvingKey<EqAffine>,
+    circuit: C,
+    instances: &[Vec<Fp>],
+) -> Result<Vec<u8>, Error> {
+    let instances: Vec<&[Fp]> = instances.iter().map(|column| &column[..]).collect();
+
+    let mut transcript = Blake2bWrite::<_, EqAffine, Challenge255<_>>::init(vec![]);
+    create_proof(
+        params,
+        pk,
+        &[circuit],
+        &[&instances[..]],
+        OsRng,
+        &mut transcript,
+    )?;
+    Ok(transcript.finalize())
+}
+
+/// Checks `proof` against the verifying key and the public instance columns.
+pub fn verify(
+    params: &Params<EqAffine>,
+    vk: &VerifyingKey<EqAffine>,
+    instances: &[Vec<Fp>],
+    proof: &[u8],
+) -> Result<(), Error> {
+    let instances: Vec<&[Fp]> = instances.iter().map(|column| &column[..]).collect();
+
+    let strategy = SingleVerifier::new(params);
+    let mut transcript = Blake2bRead::<_, EqAffine, Challenge255<_>>::init(proof);
+    verify_proof(params, vk, strategy, &[&instances[..]], &mut transcript)
+}
+
+/// Runs the whole pipeline once: setup, prove and verify against the same `instances`.
+pub fn prove_and_verify<C: Circuit<Fp>>(
+    k: u32,
+    circuit: C,
+    instances: &[Vec<Fp>],
+) -> Result<(), Error> {
+    let (params, pk) = setup(k, &circuit)?;
+    let proof = prove(&params, &pk, circuit, instances)?;
+    verify(&params, pk.get_vk(), instances, &proof)
+}
@@ -1,5 +1,9 @@
-mod example1;
-mod example1b;
-mod example2;
-mod example3;
-mod decompose_range_check;
\ No newline at end of file
+pub mod example1;
+pub mod example1b;
+pub mod example2;
+pub mod example3;
+pub mod decompose_range_check;
+pub mod bounded;
+pub mod canonical;
+pub mod signed;
+pub mod registry;
@@ -0,0 +1,380 @@
+use ff::PrimeField;
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter, Region, Value},
+    plonk::{Advice, Column, ConstraintSystem, Constraints, Error, Expression, Selector},
+    poly::Rotation,
+};
+
+use super::{
+    decompose_range_check::{
+        table::WindowTableConfig, RunningSumDecomposeChip, RunningSumDecomposeConfig,
+    },
+    example1::range_check,
+};
+
+// v < N for an arbitrary bound 1 <= N <= 2^K, with two K-bit checks:
+//
+//        value        | q_bounded | q_bits
+//   ------------------------------------------
+//          v          |     1     |   1
+//    v + 2^K - N      |     0     |   1
+//
+//   q_bounded · (value_next - (value_cur + 2^K - N)) = 0
+//
+// v < 2^K makes v an integer, and then v + 2^K - N < 2^K holds exactly when v < N
+// (both sides stay far below p, nothing wraps). The K-bit checks use either strategy
+// of the other examples: the range-check polynomial of example1 (degree 2^K + 1, small
+// K only) or a lookup into a table of the K-bit words as in example2.
+//
+// Bounds too large for a table (e.g. a field-dependent one) work the same way, with
+// both rows copied into a RunningSumDecomposeChip of K one-bit windows instead. Its
+// table is just {0, 1}, and K only has to stay below the field's capacity.
+
+/// How the two K-bit checks are done.
+#[derive(Debug, Clone)]
+pub enum BitsCheck<F: PrimeField, const K: usize> {
+    /// `x · (1 - x) · ... · (2^K - 1 - x) = 0`.
+    Polynomial,
+    /// `x` in the table, which the circuit owning it loads once.
+    Lookup(WindowTableConfig<F, K>),
+    /// `x` decomposed into K bits, in regions of its own.
+    RunningSum(RunningSumDecomposeConfig<F, 1, K>),
+}
+
+#[derive(Debug, Clone)]
+enum Bits<F: PrimeField, const K: usize> {
+    // enabled on both rows of the bounded region
+    Selector(Selector),
+    RunningSum(RunningSumDecomposeConfig<F, 1, K>),
+}
+
+#[derive(Debug, Clone)]
+pub struct BoundedRangeCheckConfig<F: PrimeField, const K: usize> {
+    q_bounded: Selector,
+    bits: Bits<F, K>,
+    value: Column<Advice>,
+    bound: F,
+}
+
+pub struct BoundedRangeCheckChip<F: PrimeField, const K: usize> {
+    config: BoundedRangeCheckConfig<F, K>,
+}
+
+impl<F: PrimeField, const K: usize> BoundedRangeCheckChip<F, K> {
+    pub fn construct(config: BoundedRangeCheckConfig<F, K>) -> Self {
+        Self { config }
+    }
+
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        value: Column<Advice>,
+        bound: F,
+        strategy: BitsCheck<F, K>,
+    ) -> BoundedRangeCheckConfig<F, K> {
+        assert!(
+            K + 1 < F::CAPACITY as usize,
+            "2^(K+1) must fit in the field"
+        );
+        assert!(
+            fits_in_bits(Self::two_pow_k() - bound, K),
+            "the bound must be in 1..=2^K"
+        );
+        let q_bounded = meta.selector();
+        meta.enable_equality(value);
+
+        meta.create_gate("v + 2^K - N", |meta| {
+            let q = meta.query_selector(q_bounded);
+            let v = meta.query_advice(value, Rotation::cur());
+            let shifted = meta.query_advice(value, Rotation::next());
+            let offset = Expression::Constant(Self::two_pow_k() - bound);
+            Constraints::with_selector(q, [("shifted", shifted - (v + offset))])
+        });
+
+        let bits = match strategy {
+            BitsCheck::Polynomial => {
+                let q_bits = meta.selector();
+                meta.create_gate("K-bit check", |meta| {
+                    let q = meta.query_selector(q_bits);
+                    let value = meta.query_advice(value, Rotation::cur());
+                    Constraints::with_selector(q, [("K-bit check", range_check(1 << K, value))])
+                });
+                Bits::Selector(q_bits)
+            }
+            BitsCheck::Lookup(table) => {
+                let q_bits = meta.complex_selector();
+                meta.lookup(|meta| {
+                    let q = meta.query_selector(q_bits);
+                    let value = meta.query_advice(value, Rotation::cur());
+                    vec![(q * value, table.value)]
+                });
+                Bits::Selector(q_bits)
+            }
+            BitsCheck::RunningSum(decompose) => Bits::RunningSum(decompose),
+        };
+
+        BoundedRangeCheckConfig {
+            q_bounded,
+            bits,
+            value,
+            bound,
+        }
+    }
+
+    fn two_pow_k() -> F {
+        F::from(2).pow([K as u64])
+    }
+
+    /// Checks a fresh witness against the bound.
+    pub fn witness_check(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: Value<F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let rows = layouter.assign_region(
+            || "v < N",
+            |mut region| {
+                let v = region.assign_advice(|| "v", self.config.value, 0, || value)?;
+                self.shift(&mut region, v)
+            },
+        )?;
+        self.check_bits(layouter, rows)
+    }
+
+    /// Checks a copy of `value` against the bound.
+    pub fn copy_check(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let rows = layouter.assign_region(
+            || "v < N",
+            |mut region| {
+                let v = value.copy_advice(|| "v", &mut region, self.config.value, 0)?;
+                self.shift(&mut region, v)
+            },
+        )?;
+        self.check_bits(layouter, rows)
+    }
+
+    fn shift(
+        &self,
+        region: &mut Region<'_, F>,
+        v: AssignedCell<F, F>,
+    ) -> Result<[AssignedCell<F, F>; 2], Error> {
+        let config = &self.config;
+        config.q_bounded.enable(region, 0)?;
+        if let Bits::Selector(q_bits) = &config.bits {
+            q_bits.enable(region, 0)?;
+            q_bits.enable(region, 1)?;
+        }
+
+        let shifted = v.value().map(|v| *v + Self::two_pow_k() - config.bound);
+        let shifted = region.assign_advice(|| "v + 2^K - N", config.value, 1, || shifted)?;
+        Ok([v, shifted])
+    }
+
+    // the running sum decomposes both rows apart, the selector strategies are done already
+    fn check_bits(
+        &self,
+        mut layouter: impl Layouter<F>,
+        [v, shifted]: [AssignedCell<F, F>; 2],
+    ) -> Result<AssignedCell<F, F>, Error> {
+        if let Bits::RunningSum(decompose) = &self.config.bits {
+            let chip = RunningSumDecomposeChip::construct(decompose.clone());
+            chip.copy_decompose(layouter.namespace(|| "v bits"), &v)?;
+            chip.copy_decompose(layouter.namespace(|| "v + 2^K - N bits"), &shifted)?;
+        }
+        Ok(v)
+    }
+}
+
+// pasta reprs are little-endian
+pub(crate) fn fits_in_bits<F: PrimeField>(value: F, bits: usize) -> bool {
+    let repr = value.to_repr();
+    repr.as_ref()
+        .iter()
+        .enumerate()
+        .all(|(i, byte)| match bits.checked_sub(8 * i) {
+            Some(rest) if rest >= 8 => true,
+            Some(rest) => byte >> rest == 0,
+            None => *byte == 0,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rows::mock_verify;
+    use halo2_proofs::{circuit::SimpleFloorPlanner, pasta::Fp, plonk::Circuit};
+
+    // v < BOUND with K-bit checks, by lookup or by polynomial
+    #[derive(Default)]
+    struct BoundedCircuit<const K: usize, const BOUND: u64, const LOOKUP: bool> {
+        value: Value<Fp>,
+    }
+
+    impl<const K: usize, const BOUND: u64, const LOOKUP: bool> Circuit<Fp>
+        for BoundedCircuit<K, BOUND, LOOKUP>
+    {
+        type Config = (
+            BoundedRangeCheckConfig<Fp, K>,
+            Option<WindowTableConfig<Fp, K>>,
+        );
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let value = meta.advice_column();
+            let table = LOOKUP.then(|| WindowTableConfig::configure(meta));
+            let strategy = match table.clone() {
+                Some(table) => BitsCheck::Lookup(table),
+                None => BitsCheck::Polynomial,
+            };
+            let config = BoundedRangeCheckChip::configure(meta, value, Fp::from(BOUND), strategy);
+            (config, table)
+        }
+
+        fn synthesize(
+            &self,
+            (config, table): Self::Config,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            if let Some(table) = table {
+                table.load(&mut layouter)?;
+            }
+            let chip = BoundedRangeCheckChip::construct(config);
+            chip.witness_check(layouter.namespace(|| "v < N"), self.value)?;
+            Ok(())
+        }
+    }
+
+    fn check<const K: usize, const BOUND: u64, const LOOKUP: bool>(value: Fp) -> bool {
+        let circuit = BoundedCircuit::<K, BOUND, LOOKUP> {
+            value: Value::known(value),
+        };
+        mock_verify(&circuit, vec![])
+    }
+
+    #[test]
+    fn test_bounded_polynomial() {
+        for v in 0..10 {
+            assert_eq!(check::<3, 5, false>(Fp::from(v)), v < 5, "{v} < 5");
+            assert_eq!(check::<3, 8, false>(Fp::from(v)), v < 8, "{v} < 8");
+            assert_eq!(check::<2, 3, false>(Fp::from(v)), v < 3, "{v} < 3");
+        }
+        assert!(!check::<3, 5, false>(-Fp::one()));
+    }
+
+    #[test]
+    fn test_bounded_lookup() {
+        for v in [0, 1, 500, 998, 999] {
+            assert!(check::<10, 1000, true>(Fp::from(v)), "{v} < 1000");
+        }
+        for v in [1000, 1001, 1023, 1024, 2023, 1 << 20] {
+            assert!(!check::<10, 1000, true>(Fp::from(v)), "{v} >= 1000");
+        }
+        // p - 1 and p - 1000 are not below the bound, whatever their shifted value is
+        for v in [-Fp::one(), -Fp::from(1000), -Fp::from(24)] {
+            assert!(!check::<10, 1000, true>(v));
+        }
+        // a bound of 2^K degenerates to a plain K-bit check
+        assert!(check::<10, 1024, true>(Fp::from(1023)));
+        assert!(!check::<10, 1024, true>(Fp::from(1024)));
+    }
+
+    // v < 2^EXP + OFFSET, both rows decomposed one bit per window
+    #[derive(Default)]
+    struct LargeBoundCircuit<const K: usize, const EXP: u64, const OFFSET: u64> {
+        value: Value<Fp>,
+    }
+
+    impl<const K: usize, const EXP: u64, const OFFSET: u64> Circuit<Fp>
+        for LargeBoundCircuit<K, EXP, OFFSET>
+    {
+        type Config = (BoundedRangeCheckConfig<Fp, K>, WindowTableConfig<Fp, 1>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let [value, z, k] = [(); 3].map(|_| meta.advice_column());
+            let table = WindowTableConfig::configure(meta);
+            let decompose = RunningSumDecomposeChip::configure(meta, [z, k], table.clone());
+            let bound = two_pow(EXP) + Fp::from(OFFSET);
+            let strategy = BitsCheck::RunningSum(decompose);
+            let config = BoundedRangeCheckChip::configure(meta, value, bound, strategy);
+            (config, table)
+        }
+
+        fn synthesize(
+            &self,
+            (config, table): Self::Config,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            table.load(&mut layouter)?;
+            let chip = BoundedRangeCheckChip::construct(config);
+            chip.witness_check(layouter.namespace(|| "v < N"), self.value)?;
+            Ok(())
+        }
+    }
+
+    fn two_pow(exp: u64) -> Fp {
+        (0..exp).fold(Fp::one(), |x, _| x + x)
+    }
+
+    fn check_large<const K: usize, const EXP: u64, const OFFSET: u64>(value: Fp) -> bool {
+        let circuit = LargeBoundCircuit::<K, EXP, OFFSET> {
+            value: Value::known(value),
+        };
+        mock_verify(&circuit, vec![])
+    }
+
+    #[test]
+    fn test_bounded_running_sum() {
+        // N = 2^20 + 1000, above any table
+        let n = Fp::from((1 << 20) + 1000);
+        for v in [Fp::zero(), Fp::from(1 << 16), n - Fp::one()] {
+            assert!(check_large::<21, 20, 1000>(v));
+        }
+        for v in [n, n + Fp::one(), Fp::from(1 << 21), -Fp::one(), -n] {
+            assert!(!check_large::<21, 20, 1000>(v));
+        }
+
+        // N = 2^250 + 3, as large as the field allows
+        let n = two_pow(250) + Fp::from(3);
+        assert!(check_large::<251, 250, 3>(n - Fp::one()));
+        assert!(check_large::<251, 250, 3>(Fp::from(u64::MAX)));
+        for v in [n, two_pow(251), -Fp::one()] {
+            assert!(!check_large::<251, 250, 3>(v));
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "the bound must be in 1..=2^K")]
+    fn test_bound_too_large() {
+        let mut meta = ConstraintSystem::<Fp>::default();
+        let value = meta.advice_column();
+        BoundedRangeCheckChip::<Fp, 3>::configure(
+            &mut meta,
+            value,
+            Fp::from(9),
+            BitsCheck::Polynomial,
+        );
+    }
+
+    #[test]
+    fn test_fits_in_bits() {
+        assert!(fits_in_bits(Fp::zero(), 0));
+        assert!(!fits_in_bits(Fp::one(), 0));
+        assert!(fits_in_bits(Fp::from(255), 8));
+        assert!(!fits_in_bits(Fp::from(256), 8));
+        assert!(fits_in_bits(Fp::from(1023), 10));
+        assert!(!fits_in_bits(Fp::from(1024), 10));
+        assert!(!fits_in_bits(-Fp::one(), 64));
+    }
+}
@@ -0,0 +1,398 @@
+use ff::PrimeField;
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter, Value},
+    plonk::{Advice, Column, ConstraintSystem, Constraints, Error, Expression, Selector},
+    poly::Rotation,
+};
+
+use super::{
+    bounded::fits_in_bits,
+    decompose_range_check::{
+        low_bits, table::WindowTableConfig, Decomposition, RunningSumDecomposeChip,
+        RunningSumDecomposeConfig,
+    },
+};
+use crate::boolean::bool_check;
+
+// The canonical decomposition of a whole field element into NUM_WINDOWS windows of
+// WINDOW_BITS bits, with WINDOW_BITS · NUM_WINDOWS = n, the bit size of p.
+//
+// The running sum alone proves α ≡ α' = Σ k_i · 2^(iK) with α' < 2^n. For Pallas and
+// Vesta (n = 255) both α' = α and α' = α + p fit, so we also need α' < p. Write
+//
+//   p = 2^(n-1) + t,   t < 2^B,   B = K · LOW_WINDOWS
+//
+// and b for bit n-1 of α', the top bit of the top window. If b = 0 then α' < p already.
+// If b = 1, α' < p means bits B..n-2 are zero and the low B bits are below t:
+//
+//   z_J = 2^(n-1-B)                     (z_J = α' >> B, J = LOW_WINDOWS)
+//   α' - z_J · 2^B + 2^B - t < 2^B      (the bounded check of range_check::bounded)
+//
+// One extra region holds everything the checks need, copied from the decomposition:
+//
+//      z      |   k   | q_canonical
+//   -----------------------------------
+//      α      |   b   |     1
+//     z_J     |  top  |     0
+//   shifted   |       |     0
+//
+//   b · (1 - b)                                       = 0
+//   2 · (top - b · 2^(K-1))                           ∈ table   (b is the top bit)
+//   b · (z_J - 2^(n-1-B))                             = 0
+//   shifted - (α - z_J · 2^B + b · (2^B - t))         = 0
+//
+// and shifted is decomposed again into LOW_WINDOWS windows, i.e. shifted < 2^B.
+
+#[derive(Debug, Clone)]
+pub struct CanonicalDecomposeConfig<
+    F: PrimeField,
+    const WINDOW_BITS: usize,
+    const NUM_WINDOWS: usize,
+    const LOW_WINDOWS: usize,
+> {
+    q_canonical: Selector,
+    z: Column<Advice>,
+    k: Column<Advice>,
+    full: RunningSumDecomposeConfig<F, WINDOW_BITS, NUM_WINDOWS>,
+    low: RunningSumDecomposeConfig<F, WINDOW_BITS, LOW_WINDOWS>,
+}
+
+pub struct CanonicalDecomposeChip<
+    F: PrimeField,
+    const WINDOW_BITS: usize,
+    const NUM_WINDOWS: usize,
+    const LOW_WINDOWS: usize,
+> {
+    config: CanonicalDecomposeConfig<F, WINDOW_BITS, NUM_WINDOWS, LOW_WINDOWS>,
+}
+
+impl<
+        F: PrimeField,
+        const WINDOW_BITS: usize,
+        const NUM_WINDOWS: usize,
+        const LOW_WINDOWS: usize,
+    > CanonicalDecomposeChip<F, WINDOW_BITS, NUM_WINDOWS, LOW_WINDOWS>
+{
+    pub fn construct(
+        config: CanonicalDecomposeConfig<F, WINDOW_BITS, NUM_WINDOWS, LOW_WINDOWS>,
+    ) -> Self {
+        Self { config }
+    }
+
+    /// `table` is only configured here, the circuit owning it loads it once.
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        [z, k]: [Column<Advice>; 2],
+        table: WindowTableConfig<F, WINDOW_BITS>,
+    ) -> CanonicalDecomposeConfig<F, WINDOW_BITS, NUM_WINDOWS, LOW_WINDOWS> {
+        assert_eq!(
+            WINDOW_BITS * NUM_WINDOWS,
+            F::NUM_BITS as usize,
+            "the windows must cover the field exactly"
+        );
+        assert!(
+            Self::low_width() + 1 < F::CAPACITY as usize
+                && fits_in_bits(Self::t(), Self::low_width()),
+            "p - 2^(n-1) must fit in the low windows"
+        );
+        let full = RunningSumDecomposeChip::configure(meta, [z, k], table.clone());
+        let low = RunningSumDecomposeChip::configure(meta, [z, k], table.clone());
+        let q_canonical = meta.complex_selector();
+
+        meta.lookup(|meta| {
+            let q = meta.query_selector(q_canonical);
+            let b = meta.query_advice(k, Rotation::cur());
+            let top = meta.query_advice(k, Rotation::next());
+            let half = Expression::Constant(pow2(WINDOW_BITS - 1));
+            let rest = top - b * half;
+            vec![(q * Expression::Constant(F::from(2)) * rest, table.value)]
+        });
+
+        meta.create_gate("canonical", |meta| {
+            let q = meta.query_selector(q_canonical);
+            let alpha = meta.query_advice(z, Rotation::cur());
+            let z_j = meta.query_advice(z, Rotation::next());
+            let shifted = meta.query_advice(z, Rotation(2));
+            let b = meta.query_advice(k, Rotation::cur());
+
+            let n = F::NUM_BITS as usize;
+            let two_pow_b = Expression::Constant(pow2(Self::low_width()));
+            let high = Expression::Constant(pow2(n - 1 - Self::low_width()));
+            let offset = Expression::Constant(pow2(Self::low_width()) - Self::t());
+
+            Constraints::with_selector(
+                q,
+                [
+                    ("b is boolean", bool_check(b.clone())),
+                    ("b = 1 => z_J = 2^(n-1-B)", b.clone() * (z_j.clone() - high)),
+                    ("shifted", shifted - (alpha - z_j * two_pow_b + b * offset)),
+                ],
+            )
+        });
+
+        CanonicalDecomposeConfig {
+            q_canonical,
+            z,
+            k,
+            full,
+            low,
+        }
+    }
+
+    // B
+    fn low_width() -> usize {
+        WINDOW_BITS * LOW_WINDOWS
+    }
+
+    // t = p - 2^(n-1), i.e. -2^(n-1) in the field
+    fn t() -> F {
+        -pow2::<F>(F::NUM_BITS as usize - 1)
+    }
+
+    /// Decomposes `value` into its canonical windows, least significant first.
+    pub fn decompose(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: &AssignedCell<F, F>,
+    ) -> Result<Decomposition<F>, Error> {
+        let full = RunningSumDecomposeChip::construct(self.config.full.clone())
+            .copy_decompose(layouter.namespace(|| "windows"), value)?;
+        self.check_canonical(layouter.namespace(|| "canonical"), &full)?;
+        Ok(full)
+    }
+
+    fn check_canonical(
+        &self,
+        mut layouter: impl Layouter<F>,
+        full: &Decomposition<F>,
+    ) -> Result<(), Error> {
+        let config = &self.config;
+        let alpha = full.value();
+        let z_j = &full.z[LOW_WINDOWS];
+        let top = &full.windows[NUM_WINDOWS - 1];
+
+        let shifted = layouter.assign_region(
+            || "canonical",
+            |mut region| {
+                config.q_canonical.enable(&mut region, 0)?;
+                let alpha = alpha.copy_advice(|| "alpha", &mut region, config.z, 0)?;
+                let z_j = z_j.copy_advice(|| "z_J", &mut region, config.z, 1)?;
+                top.copy_advice(|| "top", &mut region, config.k, 1)?;
+
+                let b = top
+                    .value()
+                    .map(|top| F::from(low_bits(*top, WINDOW_BITS) >> (WINDOW_BITS - 1)));
+                region.assign_advice(|| "b", config.k, 0, || b)?;
+
+                let two_pow_b = Value::known(pow2(Self::low_width()));
+                let offset = Value::known(pow2(Self::low_width()) - Self::t());
+                let shifted =
+                    alpha.value().copied() - z_j.value().copied() * two_pow_b + b * offset;
+                region.assign_advice(|| "shifted", config.z, 2, || shifted)
+            },
+        )?;
+
+        RunningSumDecomposeChip::construct(config.low.clone())
+            .copy_decompose(layouter.namespace(|| "shifted < 2^B"), &shifted)?;
+        Ok(())
+    }
+}
+
+fn pow2<F: PrimeField>(exp: usize) -> F {
+    F::from(2).pow([exp as u64])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rows::min_k;
+    use halo2_proofs::{
+        circuit::SimpleFloorPlanner,
+        dev::MockProver,
+        pasta::{Fp, Fq},
+        plonk::{Circuit, Instance},
+    };
+
+    // the little-endian bytes of the modulus
+    fn modulus<F: PrimeField>() -> Vec<u8> {
+        let hex = F::MODULUS.trim_start_matches("0x");
+        let mut bytes: Vec<u8> = (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+            .collect();
+        bytes.reverse();
+        bytes
+    }
+
+    // α + p as little-endian bytes, for α + p < 2^n
+    fn plus_modulus<F: PrimeField>(value: F) -> Vec<u8> {
+        let mut carry = 0;
+        let repr = value.to_repr();
+        repr.as_ref()
+            .iter()
+            .zip(modulus::<F>())
+            .map(|(&a, b)| {
+                let sum = a as u16 + b as u16 + carry;
+                carry = sum >> 8;
+                sum as u8
+            })
+            .collect()
+    }
+
+    fn windows_of<F: PrimeField>(bytes: &[u8], window_bits: usize, num_windows: usize) -> Vec<F> {
+        let bit = |i: usize| (bytes[i / 8] >> (i % 8)) as u64 & 1;
+        (0..num_windows)
+            .map(|w| {
+                let window = (0..window_bits).fold(0, |acc, j| acc | bit(w * window_bits + j) << j);
+                F::from(window)
+            })
+            .collect()
+    }
+
+    #[derive(Debug, Clone)]
+    struct TestConfig<F: PrimeField, const K: usize, const W: usize, const J: usize> {
+        canonical: CanonicalDecomposeConfig<F, K, W, J>,
+        table: WindowTableConfig<F, K>,
+        input: Column<Advice>,
+        instance: Column<Instance>,
+    }
+
+    // instance: the windows of `value`
+    //
+    // `forge` decomposes α + p instead of α, which only the canonicity check rejects
+    #[derive(Default)]
+    struct CanonicalCircuit<F, const K: usize, const W: usize, const J: usize> {
+        value: Value<F>,
+        forge: bool,
+    }
+
+    impl<F: PrimeField, const K: usize, const W: usize, const J: usize> Circuit<F>
+        for CanonicalCircuit<F, K, W, J>
+    {
+        type Config = TestConfig<F, K, W, J>;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self {
+                value: Value::unknown(),
+                forge: self.forge,
+            }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let z = meta.advice_column();
+            let k = meta.advice_column();
+            let table = WindowTableConfig::configure(meta);
+            let canonical = CanonicalDecomposeChip::configure(meta, [z, k], table.clone());
+            let input = meta.advice_column();
+            let instance = meta.instance_column();
+            meta.enable_equality(input);
+            meta.enable_equality(instance);
+
+            TestConfig {
+                canonical,
+                table,
+                input,
+                instance,
+            }
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            config.table.load(&mut layouter)?;
+            let chip = CanonicalDecomposeChip::construct(config.canonical);
+
+            let value = layouter.assign_region(
+                || "value",
+                |mut region| region.assign_advice(|| "value", config.input, 0, || self.value),
+            )?;
+
+            let decomposition = if self.forge {
+                let windows = self
+                    .value
+                    .map(|value| windows_of(&plus_modulus(value), K, W));
+                let full = RunningSumDecomposeChip::construct(chip.config.full.clone())
+                    .copy_decompose_into(layouter.namespace(|| "forged"), &value, windows)?;
+                chip.check_canonical(layouter.namespace(|| "canonical"), &full)?;
+                full
+            } else {
+                chip.decompose(layouter.namespace(|| "decompose"), &value)?
+            };
+
+            for (row, window) in decomposition.windows.iter().enumerate() {
+                layouter.constrain_instance(window.cell(), config.instance, row)?;
+            }
+            Ok(())
+        }
+    }
+
+    fn check<F: PrimeField, const K: usize, const W: usize, const J: usize>(
+        value: F,
+        forge: bool,
+    ) -> bool {
+        let circuit = CanonicalCircuit::<F, K, W, J> {
+            value: Value::known(value),
+            forge,
+        };
+        let bytes = if forge {
+            plus_modulus(value)
+        } else {
+            value.to_repr().as_ref().to_vec()
+        };
+        let k = min_k(&circuit).unwrap();
+        let prover = MockProver::run(k, &circuit, vec![windows_of(&bytes, K, W)]).unwrap();
+        prover.verify().is_ok()
+    }
+
+    fn values<F: PrimeField>() -> Vec<F> {
+        let n = F::NUM_BITS as usize;
+        vec![
+            F::ZERO,
+            F::ONE,
+            F::from(12345),
+            -F::ONE,
+            -F::from(2),
+            pow2(n - 1),
+            pow2::<F>(n - 1) + F::ONE,
+            pow2::<F>(n - 1) - F::ONE,
+            -pow2::<F>(130),
+            F::from(u64::MAX).square(),
+        ]
+    }
+
+    #[test]
+    fn test_canonical_pallas() {
+        for value in values::<Fp>() {
+            assert!(check::<Fp, 3, 85, 42>(value, false), "{value:?}");
+        }
+    }
+
+    #[test]
+    fn test_canonical_vesta() {
+        for value in values::<Fq>() {
+            assert!(check::<Fq, 5, 51, 26>(value, false), "{value:?}");
+        }
+    }
+
+    #[test]
+    fn test_canonical_bits() {
+        for value in [Fp::ZERO, Fp::from(5), -Fp::ONE] {
+            assert!(check::<Fp, 1, 255, 126>(value, false), "{value:?}");
+        }
+    }
+
+    #[test]
+    fn test_canonical_rejects_plus_modulus() {
+        // α + p < 2^255 for every α < 2^254 - t
+        for value in [Fp::ZERO, Fp::ONE, Fp::from(12345), pow2(200)] {
+            assert!(!check::<Fp, 3, 85, 42>(value, true), "{value:?}");
+            assert!(!check::<Fp, 1, 255, 126>(value, true), "{value:?}");
+        }
+        for value in [Fq::ZERO, Fq::from(7), pow2(253)] {
+            assert!(!check::<Fq, 5, 51, 26>(value, true), "{value:?}");
+        }
+    }
+}
@@ -1,199 +1,272 @@
-use ff::{Field, PrimeField};
+use ff::PrimeField;
 use halo2_proofs::{
-    circuit::{AssignedCell, Layouter, SimpleFloorPlanner, Value},
-    plonk::{
-        Advice, Assigned, Circuit, Column, ConstraintSystem, Constraints, Error, Expression,
-        Selector,
-    },
+    circuit::{AssignedCell, Layouter, Region, SimpleFloorPlanner, Value},
+    plonk::{Advice, Circuit, Column, ConstraintSystem, Constraints, Error, Expression, Selector},
     poly::Rotation,
 };
-use std::marker::PhantomData;
 
 // create a submodule which is my table and use that
-mod table;
+pub mod table;
 use table::*;
 
 /// Decomposes an $n$-bit Primefield element $\alpha$ into $W$ windows, each window
 /// being a $K$-bit word, using a running sum $z$.
-/// We constrain $K \leq 3$ for this helper.
 ///     $$\alpha = k_0 + (2^K) k_1 + (2^{2K}) k_2 + ... + (2^{(W-1)K}) k_{W-1}$$
 ///
 /// $z_0$ is initialized as $\alpha$. Each successive $z_{i+1}$ is computed as
 ///                $$z_{i+1} = (z_{i} - k_i) / (2^K).$$
 /// $z_W$ is constrained to be zero.
-/// The difference between each interstitial running sum output is constrained
-/// to be $K$ bits, i.e.
-///                      `range_check`($k_i$, $2^K$),
-/// where
+/// Each window is looked up in a shared table of the $K$-bit words, i.e.
+///                      `range_check`($k_i$, $2^K$).
+///
 /// ```text
-///   range_check(word)
-///     = word * (1 - word) * (2 - word) * ... * ((range - 1) - word)
+///     z     |   k     | q_decompose | q_zero
+///   -------------------------------------------
+///    z_0    |  k_0    |      1      |   0
+///    z_1    |  k_1    |      1      |   0
+///    ...    |  ...    |     ...     |  ...
+///   z_{W-1} | k_{W-1} |      1      |   0
+///    z_W    |         |      0      |   1
 /// ```
 ///
-/// Given that the `range_check` constraint will be toggled by a selector, in
-/// practice we will have a `selector * range_check(word)` expression
-/// of degree `range + 1`.
-///
-/// This means that $2^K$ has to be at most `degree_bound - 1` in order for
-/// the range check constraint to stay within the degree bound.
+///   q_decompose · (z_cur - 2^K · z_next - k_cur) = 0
+///   q_decompose · k_cur ∈ table
+///   q_zero · z_cur = 0
 ///
-/// This is a custom built version of the decompose running sum function.
+/// So $\alpha < 2^{KW}$, provided $KW$ is below the field capacity (with $KW$ equal to
+/// the field size the windows may also spell $\alpha + p$).
+#[derive(Debug, Clone)]
+pub struct RunningSumDecomposeConfig<
+    F: PrimeField,
+    const WINDOW_BITS: usize,
+    const NUM_WINDOWS: usize,
+> {
+    q_decompose: Selector,
+    q_zero: Selector,
+    z: Column<Advice>,
+    k: Column<Advice>,
+    table: WindowTableConfig<F, WINDOW_BITS>,
+}
 
+/// The cells of one decomposition: the running sums `z_0..=z_W` (`z_0` is the value,
+/// `z_W` is zero) and the windows `k_0..k_W`, least significant first.
 #[derive(Debug, Clone)]
-/// A range-constrained value in the circuit produced by the DecomposeRangeCheckConfig.
-struct RangeConstrained<F: PrimeField>(AssignedCell<F, F>);
+pub struct Decomposition<F: PrimeField> {
+    pub z: Vec<AssignedCell<F, F>>,
+    pub windows: Vec<AssignedCell<F, F>>,
+}
 
-// RANGE is the size of the total range we want to check.
-// LOOKUP_RANGE is the size of our lookup table i.e. the max size we can lookup in one check to the table.
-// NUM_BITS is the max number of bits we want to use to represent each value in the lookup range.
-const RANGE: usize = 64;
-const NUM_BITS: usize = 3;
-const LOOKUP_RANGE: usize = 8;
-// Thus, here we decompose a number into 3-bit chunks.
+impl<F: PrimeField> Decomposition<F> {
+    pub fn value(&self) -> &AssignedCell<F, F> {
+        &self.z[0]
+    }
+}
 
-#[derive(Debug, Clone)]
-struct DecomposeRangeCheckConfig<F: PrimeField> {
-    value: Column<Advice>,
-    value_decomposed: Column<Advice>, // Assume this value perfectly decomposes
-    q_decomposed: Selector,
-    q_range_check: Selector,
-    table: RangeTableConfig<F, LOOKUP_RANGE>,
-    _marker: PhantomData<F>,
+pub struct RunningSumDecomposeChip<
+    F: PrimeField,
+    const WINDOW_BITS: usize,
+    const NUM_WINDOWS: usize,
+> {
+    config: RunningSumDecomposeConfig<F, WINDOW_BITS, NUM_WINDOWS>,
 }
 
-impl<F: PrimeField> DecomposeRangeCheckConfig<F> {
-    pub fn configure(meta: &mut ConstraintSystem<F>) -> Self {
-        let value = meta.advice_column();
-        let value_decomposed = meta.advice_column();
-        let q_decomposed = meta.selector();
-        let q_range_check = meta.complex_selector();
-        let table = RangeTableConfig::configure(meta);
-        //        value     |  value_decomposed |    q_decomposed      |   q_range_check   | range_check_table
-        //       ---------------------------------------------------------------------------------------------
-        //          v       |         v_0       |          1           |         1         |        0
-        //          -       |         v_1       |          0           |         1         |        1
-        //          -       |         v_2       |          0           |         1         |        2
-
-        // Lookup each decomposed value individually, not paying attention to bit count
+impl<F: PrimeField, const WINDOW_BITS: usize, const NUM_WINDOWS: usize>
+    RunningSumDecomposeChip<F, WINDOW_BITS, NUM_WINDOWS>
+{
+    pub fn construct(config: RunningSumDecomposeConfig<F, WINDOW_BITS, NUM_WINDOWS>) -> Self {
+        Self { config }
+    }
+
+    /// `table` is only configured here, the circuit owning it loads it once.
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        [z, k]: [Column<Advice>; 2],
+        table: WindowTableConfig<F, WINDOW_BITS>,
+    ) -> RunningSumDecomposeConfig<F, WINDOW_BITS, NUM_WINDOWS> {
+        assert!(
+            (1..=16).contains(&WINDOW_BITS),
+            "the window table has 2^WINDOW_BITS rows"
+        );
+        assert!(
+            NUM_WINDOWS > 0 && WINDOW_BITS * NUM_WINDOWS <= F::NUM_BITS as usize,
+            "the windows must fit in the field"
+        );
+        let q_decompose = meta.complex_selector();
+        let q_zero = meta.selector();
+        meta.enable_equality(z);
+        meta.enable_equality(k);
+
         meta.lookup(|meta| {
-            let q = meta.query_selector(q_range_check);
-            let decomposed_value = meta.query_advice(value_decomposed, Rotation::cur());
-            vec![(q.clone() * decomposed_value, table.value)]
+            let q = meta.query_selector(q_decompose);
+            let k = meta.query_advice(k, Rotation::cur());
+            vec![(q * k, table.value)]
         });
 
-        // Ensure that the decomposed values add up to the original value
-        meta.create_gate("decompose", |meta| {
-            let q = meta.query_selector(q_decomposed);
-            let value = meta.query_advice(value, Rotation::cur());
-            let mut decomposed_values = vec![];
-            let decomposed_parts = RANGE / LOOKUP_RANGE;
-
-            // Because we rotate up to 8 times here, this gate adds a lot of overhead.
-            // It would be much more efficient to also have a prefix sum at each step,
-            // and only cover 1-2 different rotations instead per constraint
-            for i in 0..decomposed_parts {
-                decomposed_values.push(meta.query_advice(value_decomposed, Rotation(i as i32)));
-            }
-
-            // Combines the decomposed values into a single value and asserts equality
-            let decomposed_check =
-                |decomposed_parts: usize,
-                 value: Expression<F>,
-                 decomposed_values: Vec<Expression<F>>| {
-                    assert!(decomposed_parts > 0, "Empty value!");
-                    assert!(
-                        NUM_BITS * decomposed_parts < 64,
-                        "Value doesn't fit in bits!"
-                    );
-                    const multiplier: usize = 1 << NUM_BITS;
-                    (0..decomposed_parts).fold(
-                        Expression::Constant(F::from(0 as u64)),
-                        |expr, i| {
-                            expr + decomposed_values[i].clone()
-                                * Expression::Constant(F::from(1_u64 << (NUM_BITS * i)))
-                        },
-                    ) - value
-                };
+        meta.create_gate("running sum", |meta| {
+            let q = meta.query_selector(q_decompose);
+            let z_cur = meta.query_advice(z, Rotation::cur());
+            let z_next = meta.query_advice(z, Rotation::next());
+            let k = meta.query_advice(k, Rotation::cur());
+            let two_pow_k = Expression::Constant(F::from(1 << WINDOW_BITS));
 
             Constraints::with_selector(
                 q,
-                [(
-                    "range check",
-                    decomposed_check(decomposed_parts, value, decomposed_values),
-                )],
+                [("z_i = 2^K · z_{i+1} + k_i", z_cur - z_next * two_pow_k - k)],
             )
         });
 
-        Self {
-            value,
-            value_decomposed,
-            q_decomposed,
-            q_range_check,
+        meta.create_gate("z_W = 0", |meta| {
+            let q = meta.query_selector(q_zero);
+            let z = meta.query_advice(z, Rotation::cur());
+            Constraints::with_selector(q, [("z_W = 0", z)])
+        });
+
+        RunningSumDecomposeConfig {
+            q_decompose,
+            q_zero,
+            z,
+            k,
             table,
-            _marker: PhantomData,
         }
     }
 
-    // Note that the two types of region.assign_advice calls happen together so that it is the same region
-    pub fn assign_value(&self, mut layouter: impl Layouter<F>, value: u128) -> Result<bool, Error> {
+    /// Decomposes a fresh witness.
+    pub fn witness_decompose(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: Value<F>,
+    ) -> Result<Decomposition<F>, Error> {
         layouter.assign_region(
-            || "Assign value",
+            || "decompose",
             |mut region| {
-                let offset = 0;
-
-                // Enable q_range_check
-                self.q_decomposed.enable(&mut region, offset)?;
-
-                // Assign value
-                region.assign_advice(
-                    || "value",
-                    self.value,
-                    offset,
-                    || Value::known(F::from_u128(value)),
-                )?;
-
-                // Enable q_decomposed
-                let decomposed_parts = RANGE / LOOKUP_RANGE;
-                let mut decompose_in_progress = value;
-                for i in 0..decomposed_parts {
-                    // offset = i;
-                    self.q_range_check.enable(&mut region, i)?;
-                    let decomposed_val = decompose_in_progress % { 1 << (i * NUM_BITS) };
-                    region.assign_advice(
-                        || format!("decomposed_value {:?}", i),
-                        self.value_decomposed,
-                        i,
-                        || Value::known(F::from_u128(decomposed_val)),
-                    )?;
-                    decompose_in_progress = decompose_in_progress >> (i * NUM_BITS);
-                }
-                Ok(true)
+                let z_0 = region.assign_advice(|| "z_0", self.config.z, 0, || value)?;
+                self.decompose(&mut region, z_0, value.map(Self::windows))
             },
         )
     }
+
+    /// Decomposes a copy of `value`.
+    pub fn copy_decompose(
+        &self,
+        layouter: impl Layouter<F>,
+        value: &AssignedCell<F, F>,
+    ) -> Result<Decomposition<F>, Error> {
+        let windows = value.value().map(|value| Self::windows(*value));
+        self.copy_with_windows(layouter, value, windows)
+    }
+
+    /// Same as `copy_decompose` with the windows chosen by the caller, for the tests of
+    /// chips built on this one to check that bad windows are rejected.
+    #[cfg(test)]
+    pub(crate) fn copy_decompose_into(
+        &self,
+        layouter: impl Layouter<F>,
+        value: &AssignedCell<F, F>,
+        windows: Value<Vec<F>>,
+    ) -> Result<Decomposition<F>, Error> {
+        self.copy_with_windows(layouter, value, windows)
+    }
+
+    fn copy_with_windows(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: &AssignedCell<F, F>,
+        windows: Value<Vec<F>>,
+    ) -> Result<Decomposition<F>, Error> {
+        layouter.assign_region(
+            || "decompose",
+            |mut region| {
+                let z_0 = value.copy_advice(|| "z_0", &mut region, self.config.z, 0)?;
+                self.decompose(&mut region, z_0, windows.clone())
+            },
+        )
+    }
+
+    // k_i is the low K bits of z_i. For an out-of-range value z_W ends up non-zero
+    fn windows(value: F) -> Vec<F> {
+        let two_pow_k_inv = F::from(1 << WINDOW_BITS).invert().unwrap();
+        let mut z = value;
+        (0..NUM_WINDOWS)
+            .map(|_| {
+                let k = F::from(low_bits(z, WINDOW_BITS));
+                z = (z - k) * two_pow_k_inv;
+                k
+            })
+            .collect()
+    }
+
+    fn decompose(
+        &self,
+        region: &mut Region<'_, F>,
+        z_0: AssignedCell<F, F>,
+        windows: Value<Vec<F>>,
+    ) -> Result<Decomposition<F>, Error> {
+        let config = &self.config;
+        let two_pow_k_inv = F::from(1 << WINDOW_BITS).invert().unwrap();
+
+        let mut z = vec![z_0];
+        let mut cells = vec![];
+        for i in 0..NUM_WINDOWS {
+            config.q_decompose.enable(region, i)?;
+
+            let z_i = z[i].value().copied();
+            let k_i = windows.as_ref().map(|windows| windows[i]);
+            let z_next = (z_i - k_i) * Value::known(two_pow_k_inv);
+
+            cells.push(region.assign_advice(|| format!("k_{i}"), config.k, i, || k_i)?);
+            z.push(region.assign_advice(|| format!("z_{}", i + 1), config.z, i + 1, || z_next)?);
+        }
+        config.q_zero.enable(region, NUM_WINDOWS)?;
+
+        Ok(Decomposition { z, windows: cells })
+    }
+}
+
+// pasta reprs are little-endian
+pub(crate) fn low_bits<F: PrimeField>(value: F, bits: usize) -> u64 {
+    let repr = value.to_repr();
+    let mut word = [0u8; 8];
+    word.copy_from_slice(&repr.as_ref()[..8]);
+    u64::from_le_bytes(word) & ((1 << bits) - 1)
 }
+
+/// Proves `value < 2^(WINDOW_BITS · NUM_WINDOWS)`, by default `value` in 0..64 as
+/// two 3-bit windows.
 #[derive(Default, Clone)]
-struct DecomposeRangeCheckCircuit<F: PrimeField> {
-    // Since this is only relevant for the witness, we can opt to make this whatever convenient type we want
-    pub value: u128,
-    _marker: PhantomData<F>,
+pub struct DecomposeRangeCheckCircuit<
+    F: PrimeField,
+    const WINDOW_BITS: usize = 3,
+    const NUM_WINDOWS: usize = 2,
+> {
+    pub value: Value<F>,
 }
 
-impl<F: PrimeField> Circuit<F> for DecomposeRangeCheckCircuit<F> {
-    type Config = DecomposeRangeCheckConfig<F>;
+impl<F: PrimeField, const WINDOW_BITS: usize, const NUM_WINDOWS: usize>
+    DecomposeRangeCheckCircuit<F, WINDOW_BITS, NUM_WINDOWS>
+{
+    pub fn new(value: u128) -> Self {
+        Self {
+            value: Value::known(F::from_u128(value)),
+        }
+    }
+}
+
+impl<F: PrimeField, const WINDOW_BITS: usize, const NUM_WINDOWS: usize> Circuit<F>
+    for DecomposeRangeCheckCircuit<F, WINDOW_BITS, NUM_WINDOWS>
+{
+    type Config = RunningSumDecomposeConfig<F, WINDOW_BITS, NUM_WINDOWS>;
     type FloorPlanner = SimpleFloorPlanner;
 
     // Circuit without witnesses, called only during key generation
     fn without_witnesses(&self) -> Self {
-        Self {
-            value: 0,
-            _marker: PhantomData,
-        }
+        Self::default()
     }
 
     fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
-        let config = DecomposeRangeCheckConfig::configure(meta);
-        config
+        let z = meta.advice_column();
+        let k = meta.advice_column();
+        let table = WindowTableConfig::configure(meta);
+        RunningSumDecomposeChip::configure(meta, [z, k], table)
     }
 
     fn synthesize(
@@ -202,59 +275,120 @@ impl<F: PrimeField> Circuit<F> for DecomposeRangeCheckCircuit<F> {
         mut layouter: impl Layouter<F>,
     ) -> Result<(), Error> {
         config.table.load(&mut layouter)?;
-        print!("Synthesize being called...\n");
-        assert!(
-            RANGE % LOOKUP_RANGE == 0,
-            "Range must be a multiple of lookup range"
-        );
-        let mut value = config.assign_value(layouter.namespace(|| "Assign all values"), self.value);
+        let chip = RunningSumDecomposeChip::construct(config);
+        chip.witness_decompose(layouter.namespace(|| "Assign all values"), self.value)?;
         Ok(())
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use halo2_proofs::{
-        circuit::floor_planner::V1,
-        dev::{FailureLocation, MockProver, VerifyFailure},
-        pasta::Fp,
-        plonk::{Any, Circuit},
-    };
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
 
     use super::*;
+    use crate::{
+        proof::prove_and_verify,
+        rows::{min_k, mock_verify},
+    };
+
+    fn check<const K: usize, const W: usize>(value: Fp) -> bool {
+        let circuit = DecomposeRangeCheckCircuit::<Fp, K, W> {
+            value: Value::known(value),
+        };
+        mock_verify(&circuit, vec![])
+    }
 
     #[test]
     fn test_range_check_pass() {
-        let k = 10; // 8, 128, etc
-
-        // Successful cases
-        for i in 0..RANGE {
-            let i = 0;
-            let circuit = DecomposeRangeCheckCircuit::<Fp> {
-                value: i as u128,
-                _marker: PhantomData,
-            };
-
-            let prover = MockProver::run(k, &circuit, vec![]).unwrap();
-            prover.assert_satisfied();
+        // every value in 0..64
+        for i in 0..64 {
+            assert!(check::<3, 2>(Fp::from(i)), "{i}");
+        }
+        for i in [0, 1, 15, 16, 255, 256, 4095] {
+            assert!(check::<4, 3>(Fp::from(i)), "{i}");
         }
     }
 
     #[test]
     fn test_range_check_fail() {
-        let k = 10;
-        // Out-of-range `value = 8`
-        let circuit = DecomposeRangeCheckCircuit::<Fp> {
-            value: RANGE as u128,
-            _marker: PhantomData,
-        };
-        let prover = MockProver::run(k, &circuit, vec![]).unwrap();
-        match prover.verify() {
-            Err(e) => {
-                println!("Error successfully achieved!");
-            }
-            _ => assert_eq!(1, 0),
+        for i in [64, 65, 127, 128, 4096, u64::MAX] {
+            assert!(!check::<3, 2>(Fp::from(i)), "{i}");
+        }
+        for i in [4096, 4097, 8191, 1 << 20] {
+            assert!(!check::<4, 3>(Fp::from(i)), "{i}");
         }
+        // -1 = p - 1 does not wrap around into range
+        assert!(!check::<3, 2>(-Fp::one()));
+        assert!(!check::<4, 3>(-Fp::one()));
+    }
+
+    // windows chosen by hand, bypassing the witness computation
+    struct ForgedCircuit {
+        value: u64,
+        windows: [u64; 2],
+    }
+
+    impl Circuit<Fp> for ForgedCircuit {
+        type Config = RunningSumDecomposeConfig<Fp, 3, 2>;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self { ..*self }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            DecomposeRangeCheckCircuit::<Fp>::configure(meta)
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            config.table.load(&mut layouter)?;
+            layouter.assign_region(
+                || "forged",
+                |mut region| {
+                    let mut z = Fp::from(self.value);
+                    for (i, &k) in self.windows.iter().enumerate() {
+                        config.q_decompose.enable(&mut region, i)?;
+                        region.assign_advice(|| "z", config.z, i, || Value::known(z))?;
+                        region.assign_advice(|| "k", config.k, i, || Value::known(Fp::from(k)))?;
+                        z = (z - Fp::from(k)) * ff::Field::invert(&Fp::from(8)).unwrap();
+                    }
+                    config.q_zero.enable(&mut region, 2)?;
+                    region.assign_advice(|| "z", config.z, 2, || Value::known(z))?;
+                    Ok(())
+                },
+            )
+        }
+    }
+
+    #[test]
+    fn test_range_check_forged_windows() {
+        let k = min_k(&DecomposeRangeCheckCircuit::<Fp>::default()).unwrap();
+        for (value, windows, ok) in [
+            (13, [5, 1], true),
+            // 13 = 13 + 8 · 0, but 13 is not a 3-bit window
+            (13, [13, 0], false),
+            // 64 = 0 + 8 · 8, but 8 is not a 3-bit window
+            (64, [0, 8], false),
+            // the windows spell 63, not 64
+            (64, [7, 7], false),
+        ] {
+            let prover = MockProver::run(k, &ForgedCircuit { value, windows }, vec![]).unwrap();
+            assert_eq!(prover.verify().is_ok(), ok, "{value} = {windows:?}");
+        }
+    }
+
+    #[test]
+    fn test_decompose_range_check_real_prover() {
+        let circuit = DecomposeRangeCheckCircuit::<Fp>::new(63);
+        let k = min_k(&circuit).unwrap();
+        prove_and_verify(k, circuit, &[]).unwrap();
+
+        let circuit = DecomposeRangeCheckCircuit::<Fp>::new(64);
+        assert!(prove_and_verify(k, circuit, &[]).is_err());
     }
 
     // $ cargo test --release --all-features print_decompose_range_check_1
@@ -270,12 +404,9 @@ mod tests {
             .titled("Range Check 1 Layout", ("sans-serif", 60))
             .unwrap();
 
-        let circuit = DecomposeRangeCheckCircuit::<Fp> {
-            value: 2 as u128,
-            _marker: PhantomData,
-        };
+        let circuit = DecomposeRangeCheckCircuit::<Fp>::new(2);
         halo2_proofs::dev::CircuitLayout::default()
-            .render(3, &circuit, &root)
+            .render(min_k(&circuit).unwrap(), &circuit, &root)
             .unwrap();
     }
-}
\ No newline at end of file
+}
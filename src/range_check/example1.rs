@@ -2,8 +2,11 @@ use std::marker::PhantomData;
 use ff::{Field, PrimeField};
 use halo2_proofs::{
     // arithmetic::FieldExt,
-    circuit::{AssignedCell, Layouter, Value},
-    plonk::{Advice, Assigned, Column, ConstraintSystem, Constraints, Error, Expression, Selector},
+    circuit::{floor_planner::V1, AssignedCell, Layouter, Value},
+    plonk::{
+        Advice, Assigned, Circuit, Column, ConstraintSystem, Constraints, Error, Expression,
+        Selector,
+    },
     poly::Rotation,
 };
 
@@ -15,15 +18,24 @@ use halo2_proofs::{
 
 #[derive(Debug, Clone)]
 /// A range-constrained value in the circuit produced by the RangeCheckConfig.
-struct RangeConstrained<F: PrimeField, const RANGE: usize>(AssignedCell<Assigned<F>, F>);
+pub struct RangeConstrained<F: PrimeField, const RANGE: usize>(pub AssignedCell<Assigned<F>, F>);
 
 #[derive(Debug, Clone)]
-struct RangeCheckConfig<F: PrimeField, const RANGE: usize> {
+pub struct RangeCheckConfig<F: PrimeField, const RANGE: usize> {
     value: Column<Advice>,
     q_range_check: Selector,
     _marker: PhantomData<F>,
 }
 
+/// Given a range R and a value v, returns the expression
+/// (v) * (1 - v) * (2 - v) * ... * (R - 1 - v)
+pub fn range_check<F: PrimeField>(range: usize, value: Expression<F>) -> Expression<F> {
+    assert!(range > 0);
+    (1..range).fold(value.clone(), |expr, i| {
+        expr * (Expression::Constant(F::from(i as u64)) - value.clone())
+    })
+}
+
 impl<F: PrimeField, const RANGE: usize> RangeCheckConfig<F, RANGE> {
     pub fn configure(meta: &mut ConstraintSystem<F>, value: Column<Advice>) -> Self {
         let q_range_check = meta.selector();
@@ -36,14 +48,6 @@ impl<F: PrimeField, const RANGE: usize> RangeCheckConfig<F, RANGE> {
             let q = meta.query_selector(q_range_check);
             let value = meta.query_advice(value, Rotation::cur());
 
-            // Given a range R and a value v, returns the expression
-            // (v) * (1 - v) * (2 - v) * ... * (R - 1 - v)
-            let range_check = |range: usize, value: Expression<F>| {
-                assert!(range > 0);
-                (1..range).fold(value.clone(), |expr, i| {
-                    expr * (Expression::Constant(F::from(i as u64)) - value.clone())
-                })
-            };
             // new API  vs  'vec![q_enable * value * is_zero_expr.clone()]'
             // constrain the expr: `range_check(RANGE, value)` is 0.
             Constraints::with_selector(q, [("range check", range_check(RANGE, value))])
@@ -84,56 +88,57 @@ impl<F: PrimeField, const RANGE: usize> RangeCheckConfig<F, RANGE> {
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use halo2_proofs::{
-        circuit::floor_planner::V1,
-        dev::{FailureLocation, MockProver, VerifyFailure},
-        pasta::Fp,
-        plonk::{Any, Circuit},
-    };
+/// Checks a single private value against `0..RANGE` with the range-check polynomial.
+#[derive(Debug, Clone, Default)]
+pub struct RangeCheckCircuit<F: PrimeField, const RANGE: usize> {
+    pub value: Value<Assigned<F>>,
+}
 
-    use super::*;
+impl<F: PrimeField, const RANGE: usize> Circuit<F> for RangeCheckCircuit<F, RANGE> {
+    type Config = RangeCheckConfig<F, RANGE>;
+    type FloorPlanner = V1;
 
-    #[derive(Default)]
-    struct MyCircuit<F: PrimeField, const RANGE: usize> {
-        value: Value<Assigned<F>>,
+    fn without_witnesses(&self) -> Self {
+        Self::default()
     }
 
-    impl<F: PrimeField, const RANGE: usize> Circuit<F> for MyCircuit<F, RANGE> {
-        type Config = RangeCheckConfig<F, RANGE>;
-        type FloorPlanner = V1;
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let value = meta.advice_column();
+        RangeCheckConfig::configure(meta, value)
+    }
 
-        fn without_witnesses(&self) -> Self {
-            Self::default()
-        }
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        config.assign(layouter.namespace(|| "Assign value"), self.value)?;
 
-        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
-            let value = meta.advice_column();
-            RangeCheckConfig::configure(meta, value)
-        }
+        Ok(())
+    }
+}
 
-        fn synthesize(
-            &self,
-            config: Self::Config,
-            mut layouter: impl Layouter<F>,
-        ) -> Result<(), Error> {
-            config.assign(layouter.namespace(|| "Assign value"), self.value)?;
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{
+        dev::{FailureLocation, MockProver, VerifyFailure},
+        pasta::Fp,
+        plonk::Any,
+    };
 
-            Ok(())
-        }
-    }
+    use super::*;
+    use crate::{proof::prove_and_verify, rows::min_k};
 
     #[test]
     fn test_range_check_1() {
-        let k = 4;
         const RANGE: usize = 8; // 3-bit value
 
         // Successful cases
         for i in 0..RANGE {
-            let circuit = MyCircuit::<Fp, RANGE> {
+            let circuit = RangeCheckCircuit::<Fp, RANGE> {
                 value: Value::known(Fp::from(i as u64).into()),
             };
+            let k = min_k(&circuit).unwrap();
 
             let prover = MockProver::run(k, &circuit, vec![]).unwrap();
             prover.assert_satisfied();
@@ -141,10 +146,10 @@ mod tests {
 
         // Out-of-range `value = 8`
         {
-            let circuit = MyCircuit::<Fp, RANGE> {
+            let circuit = RangeCheckCircuit::<Fp, RANGE> {
                 value: Value::known(Fp::from(RANGE as u64).into()),
             };
-            let prover = MockProver::run(k, &circuit, vec![]).unwrap();
+            let prover = MockProver::run(min_k(&circuit).unwrap(), &circuit, vec![]).unwrap();
             assert_eq!(
                 prover.verify(),
                 Err(vec![VerifyFailure::ConstraintNotSatisfied {
@@ -159,6 +164,22 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_range_check_1_real_prover() {
+        const RANGE: usize = 8; // 3-bit value
+
+        let circuit = RangeCheckCircuit::<Fp, RANGE> {
+            value: Value::known(Fp::from(7).into()),
+        };
+        let k = min_k(&circuit).unwrap();
+        prove_and_verify(k, circuit, &[]).unwrap();
+
+        let circuit = RangeCheckCircuit::<Fp, RANGE> {
+            value: Value::known(Fp::from(RANGE as u64).into()),
+        };
+        assert!(prove_and_verify(k, circuit, &[]).is_err());
+    }
+
     // $ cargo test --release --all-features print_range_check_1
     #[cfg(feature = "dev-graph")]
     #[test]
@@ -171,11 +192,11 @@ mod tests {
             .titled("Range Check 1 Layout", ("sans-serif", 60))
             .unwrap();
 
-        let circuit = MyCircuit::<Fp, 8> {
+        let circuit = RangeCheckCircuit::<Fp, 8> {
             value: Value::unknown(),
         };
         halo2_proofs::dev::CircuitLayout::default()
-            .render(3, &circuit, &root)
+            .render(min_k(&circuit).unwrap(), &circuit, &root)
             .unwrap();
     }
 }
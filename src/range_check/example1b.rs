@@ -30,7 +30,7 @@ use halo2_proofs::{
 // It is a bit like a morphism type in a Monoidal category (domain and codomain), or the row and column labels in a dataframe. Let's call it the FrameType
 // It can be unstructured because it is the Circuit implementer's job to translate this information into the format needed for the Layouter.
 #[derive(Clone)]
-struct MyConfig<F: PrimeField, const RANGE: usize> {
+pub struct MyConfig<F: PrimeField, const RANGE: usize> {
     advice_column: Column<Advice>, // note that this is a marker, cannot hold an element (but will be assigned a usize index). It describes the constraint system output by circuit.configure(). It tells us there is one advice column (which will need to be assigned a global column index in the matrix).
     q_range_check: Selector,       // similarly a marker and index for a Selector
     _marker: PhantomData<F>,
@@ -41,11 +41,18 @@ impl<F: PrimeField, const RANGE: usize> MyConfig<F, RANGE> {}
 
 #[derive(Default)] // Deriving Default calls Default on Value<Assigned<F>> calls impl<V> Default for Value<V> { fn default() -> Self {  Self::unknown()  }}
                    // which in turn sets value.inner: Option<V> to None
-struct MyCircuit<F: PrimeField, const RANGE: usize> {
-    assigned_value: Value<Assigned<F>>,
+pub struct MyCircuit<F: PrimeField, const RANGE: usize> {
+    pub assigned_value: Value<Assigned<F>>,
     _marker: PhantomData<F>,
 }
-impl<F: PrimeField, const RANGE: usize> MyCircuit<F, RANGE> {}
+impl<F: PrimeField, const RANGE: usize> MyCircuit<F, RANGE> {
+    pub fn new(assigned_value: Value<Assigned<F>>) -> Self {
+        Self {
+            assigned_value,
+            _marker: PhantomData,
+        }
+    }
+}
 
 // Your Circuit plays several roles and  will be passed to prover and verifier key generation, prove, and verifier.
 // Implementing the Circuit trait requires three functions:
@@ -177,12 +184,13 @@ mod tests {
     };
 
     use super::*;
+    use crate::{proof::prove_and_verify, rows::min_k};
 
     #[test]
     fn test_range_check_1() {
-        let k = 4; //2^k rows
         const RANGE: usize = 8; // 3-bit value
         let testvalue: u64 = 22;
+        let k = min_k(&MyCircuit::<Fp, RANGE>::default()).unwrap(); //2^k rows
 
         // Successful cases
         for i in 0..RANGE {
@@ -218,4 +226,22 @@ mod tests {
             );
         }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_range_check_1b_real_prover() {
+        const RANGE: usize = 8; // 3-bit value
+
+        let circuit = MyCircuit::<Fp, RANGE> {
+            assigned_value: Value::known(Fp::from(5).into()),
+            _marker: PhantomData,
+        };
+        let k = min_k(&circuit).unwrap();
+        prove_and_verify(k, circuit, &[]).unwrap();
+
+        let circuit = MyCircuit::<Fp, RANGE> {
+            assigned_value: Value::known(Fp::from(22).into()),
+            _marker: PhantomData,
+        };
+        assert!(prove_and_verify(k, circuit, &[]).is_err());
+    }
+}
@@ -1,11 +1,14 @@
 use ff::{Field, PrimeField};
 use halo2_proofs::{
-    circuit::{AssignedCell, Layouter, Value},
-    plonk::{Advice, Assigned, Column, ConstraintSystem, Constraints, Error, Expression, Selector},
+    circuit::{floor_planner::V1, AssignedCell, Layouter, Value},
+    plonk::{
+        Advice, Assigned, Circuit, Column, ConstraintSystem, Constraints, Error, Expression,
+        Selector,
+    },
     poly::Rotation,
 };
 
-mod table;
+pub mod table;
 use table::*;
 
 // This helper checks that the value witnessed in a given cell is within a given range.
@@ -21,10 +24,10 @@ use table::*;
 
 #[derive(Debug, Clone)]
 /// A range-constrained value in the circuit produced by the RangeCheckConfig.
-struct RangeConstrained<F: PrimeField, const RANGE: usize>(AssignedCell<Assigned<F>, F>);
+pub struct RangeConstrained<F: PrimeField, const RANGE: usize>(pub AssignedCell<Assigned<F>, F>);
 
 #[derive(Debug, Clone)]
-struct RangeCheckConfig<F: PrimeField, const RANGE: usize, const LOOKUP_RANGE: usize> {
+pub struct RangeCheckConfig<F: PrimeField, const RANGE: usize, const LOOKUP_RANGE: usize> {
     q_range_check: Selector, // for *small* RANGE number.
     q_lookup: Selector,      // for *large* RANGE number.
     value: Column<Advice>,
@@ -39,12 +42,21 @@ impl<F: PrimeField, const RANGE: usize, const LOOKUP_RANGE: usize>
 {
     // Remember that the configuration happen at keygen time.
     pub fn configure(meta: &mut ConstraintSystem<F>, value: Column<Advice>) -> Self {
+        // configure a lookup table. and **pass it to config**
+        let table = RangeTableConfig::configure(meta);
+        Self::configure_with_table(meta, value, table)
+    }
+
+    /// Same as `configure`, with a table shared with other chips (see `TableRegistry`).
+    pub fn configure_with_table(
+        meta: &mut ConstraintSystem<F>,
+        value: Column<Advice>,
+        table: RangeTableConfig<F, LOOKUP_RANGE>,
+    ) -> Self {
         // Toggles the range_check constraint
         let q_range_check = meta.selector();
         // Toggles the lookup argument
         let q_lookup = meta.complex_selector(); // for lookup table
-        // configure a lookup table. and **pass it to config**
-        let table = RangeTableConfig::configure(meta);
 
         // later we will return this config.
         let config = Self {
@@ -145,73 +157,74 @@ impl<F: PrimeField, const RANGE: usize, const LOOKUP_RANGE: usize>
     }
 }
 
+/// Checks a small value with the range-check polynomial and a larger one with the lookup table.
+#[derive(Debug, Clone, Default)]
+pub struct RangeCheckCircuit<F: PrimeField, const RANGE: usize, const LOOKUP_RANGE: usize> {
+    pub simple_value: Value<Assigned<F>>,
+    pub lookup_value: Value<Assigned<F>>,
+}
+
+impl<F: PrimeField, const RANGE: usize, const LOOKUP_RANGE: usize> Circuit<F>
+    for RangeCheckCircuit<F, RANGE, LOOKUP_RANGE>
+{
+    type Config = RangeCheckConfig<F, RANGE, LOOKUP_RANGE>;
+    type FloorPlanner = V1;
+
+    fn without_witnesses(&self) -> Self { Self::default() }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let value = meta.advice_column();
+        RangeCheckConfig::configure(meta, value)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        // load lookup table.
+        config.table.load(&mut layouter)?;
+
+        config.assign_simple(
+            layouter.namespace(|| "Assign simple(smaller) value"), 
+            self.simple_value
+        )?;
+        config.assign_lookup(
+            layouter.namespace(|| "Assign lookup(larger) value"),
+            self.lookup_value,
+        )?;
+        Ok(())
+    }
+}
+
 // [cfg(test)]是一个条件编译属性，意思是只有在执行 test 时，此模块代码才会被编译和执行
 // 好处是，当你在普通的编译或生产环境下构建你的程序时，测试代码不会被包括进去，
 // 从而减少了编译时间和生成的可执行文件的大小。
 #[cfg(test)]
 mod tests {
     use halo2_proofs::{
-        circuit::floor_planner::V1,
         dev::{FailureLocation, MockProver, VerifyFailure},
         pasta::Fp,
-        plonk::{Any, Circuit},
+        plonk::Any,
     };
 
     use super::*;
-
-    #[derive(Default)]
-    struct MyCircuit<F: PrimeField, const RANGE: usize, const LOOKUP_RANGE: usize> {
-        simple_value: Value<Assigned<F>>,
-        lookup_value: Value<Assigned<F>>,
-    }
-
-    impl<F: PrimeField, const RANGE: usize, const LOOKUP_RANGE: usize> Circuit<F>
-        for MyCircuit<F, RANGE, LOOKUP_RANGE>
-    {
-        type Config = RangeCheckConfig<F, RANGE, LOOKUP_RANGE>;
-        type FloorPlanner = V1;
-
-        fn without_witnesses(&self) -> Self { Self::default() }
-
-        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
-            let value = meta.advice_column();
-            RangeCheckConfig::configure(meta, value)
-        }
-
-        fn synthesize(
-            &self,
-            config: Self::Config,
-            mut layouter: impl Layouter<F>,
-        ) -> Result<(), Error> {
-            // load lookup table.
-            config.table.load(&mut layouter)?;
-
-            config.assign_simple(
-                layouter.namespace(|| "Assign simple(smaller) value"), 
-                self.simple_value
-            )?;
-            config.assign_lookup(
-                layouter.namespace(|| "Assign lookup(larger) value"),
-                self.lookup_value,
-            )?;
-            Ok(())
-        }
-    }
+    use crate::{proof::prove_and_verify, rows::min_k};
 
     #[test]
     fn test_range_check_2_lookup() {
         // in every circuit, we opt to reserve the last few rows of each advice cols 
         // for random values which are blinding factors(for zk), so `k` is always larger.
-        let k = 9;
         const RANGE: usize = 8; // 3-bit value
         const LOOKUP_RANGE: usize = 256; // 2^8, 8-bit value
+        let k = min_k(&RangeCheckCircuit::<Fp, RANGE, LOOKUP_RANGE>::default()).unwrap();
 
         // Successful cases
         for i in 0..RANGE {
             for j in 0..LOOKUP_RANGE {
                 // According to the <i, j> to construct different Circuit.
-                // MyCircuit::<Fp,.. ,..> : 指定 Constant 泛型的值
-                let circuit = MyCircuit::<Fp, RANGE, LOOKUP_RANGE> {
+                // RangeCheckCircuit::<Fp,.. ,..> : 指定 Constant 泛型的值
+                let circuit = RangeCheckCircuit::<Fp, RANGE, LOOKUP_RANGE> {
                     simple_value: Value::known(Fp::from(i as u64).into()),
                     lookup_value: Value::known(Fp::from(j as u64).into()),
                 };
@@ -223,7 +236,7 @@ mod tests {
 
         // Out-of-range `value = 8`, `lookup_value = 256`
         {
-            let circuit = MyCircuit::<Fp, RANGE, LOOKUP_RANGE> {
+            let circuit = RangeCheckCircuit::<Fp, RANGE, LOOKUP_RANGE> {
                 simple_value: Value::known(Fp::from(RANGE as u64).into()),
                 lookup_value: Value::known(Fp::from(LOOKUP_RANGE as u64).into()),
             };
@@ -251,6 +264,25 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_range_check_2_real_prover() {
+        const RANGE: usize = 8; // 3-bit value
+        const LOOKUP_RANGE: usize = 256; // 2^8, 8-bit value
+        let k = min_k(&RangeCheckCircuit::<Fp, RANGE, LOOKUP_RANGE>::default()).unwrap();
+
+        let circuit = RangeCheckCircuit::<Fp, RANGE, LOOKUP_RANGE> {
+            simple_value: Value::known(Fp::from(3).into()),
+            lookup_value: Value::known(Fp::from(200).into()),
+        };
+        prove_and_verify(k, circuit, &[]).unwrap();
+
+        let circuit = RangeCheckCircuit::<Fp, RANGE, LOOKUP_RANGE> {
+            simple_value: Value::known(Fp::from(3).into()),
+            lookup_value: Value::known(Fp::from(LOOKUP_RANGE as u64).into()),
+        };
+        assert!(prove_and_verify(k, circuit, &[]).is_err());
+    }
+
     // $ cargo test --release --all-features print_range_check_2
     #[cfg(feature = "dev-graph")]
     #[test]
@@ -263,12 +295,12 @@ mod tests {
             .titled("Range Check 2 Layout", ("sans-serif", 60))
             .unwrap();
 
-        let circuit = MyCircuit::<Fp, 8, 256> {
+        let circuit = RangeCheckCircuit::<Fp, 8, 256> {
             simple_value: Value::unknown(),
             lookup_value: Value::unknown(),
         };
         halo2_proofs::dev::CircuitLayout::default()
-            .render(9, &circuit, &root)
+            .render(min_k(&circuit).unwrap(), &circuit, &root)
             .unwrap();
     }
 }
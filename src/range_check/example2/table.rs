@@ -10,18 +10,20 @@ use halo2_proofs::{
     plonk::{ConstraintSystem, Error, TableColumn},
 };
 
-/// pub(super) 仅当前模块的父模块中可见，但不对外公开
+use crate::range_check::registry::TableRegistry;
+
+/// pub: 其他 chip (如 comparator::LtChip) 可以共享同一张查找表
 /// A lookup table of values from 0..RANGE. 
 /// TableColumn is a Fixed Column
 #[derive(Debug, Clone)]
-pub(super) struct RangeTableConfig<F: PrimeField, const RANGE: usize> {
-    pub(super) value: TableColumn, 
+pub struct RangeTableConfig<F: PrimeField, const RANGE: usize> {
+    pub value: TableColumn, 
     // 这个 struct 中存在一个与类型 F 相关的关联，即使 struct 自身并没有实际使用这个类型
     _marker: PhantomData<F>,
 }
 
 impl<F: PrimeField, const RANGE: usize> RangeTableConfig<F, RANGE> {
-    pub(super) fn configure(meta: &mut ConstraintSystem<F>) -> Self {
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> Self {
         // API to create this special fixed colum : Lookup column
         let value = meta.lookup_table_column();
 
@@ -31,28 +33,46 @@ impl<F: PrimeField, const RANGE: usize> RangeTableConfig<F, RANGE> {
         }
     }
 
+    /// The 0..RANGE column of `tables`, shared with any other chip asking for it.
+    /// `tables.load` loads it, not `load`.
+    pub fn shared(meta: &mut ConstraintSystem<F>, tables: &mut TableRegistry<F>) -> Self {
+        Self {
+            value: tables.range(meta, RANGE),
+            _marker: PhantomData,
+        }
+    }
+
     // load function assign the values to our fixed table
     // This action is performed at key gen time
-    pub(super) fn load(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
-        // firstly, for some RANGE we want to load all the values and assign it to the lookup table
-        // assign_table is a special API that only works for `lookup tables`
-        layouter.assign_table (
-            || "load range-check table",
-            |mut table| {
-                // from row_0 to row_{RANGE-1}
-                let mut offset = 0;
-                for value in 0..RANGE {
-                    table.assign_cell(
-                        || "num_bits",
-                        self.value,
-                        offset,  // row num
-                        || Value::known(F::from(value as u64)), // assigned value
-                    )?;
-                    offset += 1;  // 循环向下赋值, 直到填满 RANGE 所需的所有列
-                }
-
-                Ok(()) // return empty tuple (∵ Result<(), Error>)
-            },
-        )
+    pub fn load(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        load_range(layouter, self.value, RANGE)
     }
 }
+
+/// Assigns 0..range to `value`, the content of every range table (see `TableRegistry`).
+pub(crate) fn load_range<F: PrimeField>(
+    layouter: &mut impl Layouter<F>,
+    value: TableColumn,
+    range: usize,
+) -> Result<(), Error> {
+    // firstly, for some RANGE we want to load all the values and assign it to the lookup table
+    // assign_table is a special API that only works for `lookup tables`
+    layouter.assign_table (
+        || "load range-check table",
+        |mut table| {
+            // from row_0 to row_{RANGE-1}
+            let mut offset = 0;
+            for v in 0..range {
+                table.assign_cell(
+                    || "num_bits",
+                    value,
+                    offset,  // row num
+                    || Value::known(F::from(v as u64)), // assigned value
+                )?;
+                offset += 1;  // 循环向下赋值, 直到填满 RANGE 所需的所有列
+            }
+
+            Ok(()) // return empty tuple (∵ Result<(), Error>)
+        },
+    )
+}
@@ -1,12 +1,12 @@
 use ff::{Field, PrimeField};
 use halo2_proofs::{
-    circuit::{AssignedCell, Layouter, Value},
-    plonk::{Advice, Assigned, Column, ConstraintSystem, Error, Expression, Selector},
+    circuit::{floor_planner::V1, AssignedCell, Layouter, Value},
+    plonk::{Advice, Assigned, Circuit, Column, ConstraintSystem, Error, Expression, Selector},
     poly::Rotation,
 };
 
 // create a submodule which is my table and use that
-mod table;
+pub mod table;
 use table::*;
 
 // /// This helper uses a lookup table to check that the value witnessed in a given cell is
@@ -27,14 +27,14 @@ use table::*;
 
 #[derive(Debug, Clone)]
 /// A range-constrained value in the circuit produced by the RangeCheckConfig.
-struct RangeConstrained<F: PrimeField> {
-    num_bits: AssignedCell<Assigned<F>, F>,
-    assigned_cell: AssignedCell<Assigned<F>, F>,
+pub struct RangeConstrained<F: PrimeField> {
+    pub num_bits: AssignedCell<Assigned<F>, F>,
+    pub assigned_cell: AssignedCell<Assigned<F>, F>,
 }
 
 #[derive(Debug, Clone)]
 // WE ADD A FURTHER NUM_BITS COLUMN TO OUR CONFIG
-struct RangeCheckConfig<F: PrimeField, const NUM_BITS: usize, const RANGE: usize> {
+pub struct RangeCheckConfig<F: PrimeField, const NUM_BITS: usize, const RANGE: usize> {
     q_lookup: Selector,
     num_bits: Column<Advice>,
     value: Column<Advice>,
@@ -51,9 +51,19 @@ impl<F: PrimeField, const NUM_BITS: usize, const RANGE: usize> RangeCheckConfig<
         num_bits: Column<Advice>,
         value: Column<Advice>,
     ) -> Self {
-        let q_lookup = meta.complex_selector();  // complex_selector
         // 配置查找表 configure lookup table.
         let table = RangeTableConfig::configure(meta);
+        Self::configure_with_table(meta, num_bits, value, table)
+    }
+
+    /// Same as `configure`, with a table shared with other chips (see `TableRegistry`).
+    pub fn configure_with_table(
+        meta: &mut ConstraintSystem<F>,
+        num_bits: Column<Advice>,
+        value: Column<Advice>,
+        table: RangeTableConfig<F, NUM_BITS, RANGE>,
+    ) -> Self {
+        let q_lookup = meta.complex_selector();  // complex_selector
 
         meta.lookup(|meta| {
             let q_lookup = meta.query_selector(q_lookup);
@@ -135,61 +145,63 @@ impl<F: PrimeField, const NUM_BITS: usize, const RANGE: usize> RangeCheckConfig<
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use halo2_proofs::{circuit::floor_planner::V1, dev::MockProver, pasta::Fp, plonk::Circuit};
+/// Checks that `value` is exactly a `num_bits`-bit value with the tagged lookup table.
+#[derive(Debug, Clone, Default)]
+pub struct RangeCheckCircuit<F: PrimeField, const NUM_BITS: usize, const RANGE: usize> {
+    pub num_bits: Value<u8>,
+    pub value: Value<Assigned<F>>,
+}
 
-    use super::*;
+impl<F: PrimeField, const NUM_BITS: usize, const RANGE: usize> Circuit<F>
+    for RangeCheckCircuit<F, NUM_BITS, RANGE>
+{
+    type Config = RangeCheckConfig<F, NUM_BITS, RANGE>;
+    type FloorPlanner = V1;
 
-    #[derive(Default)]
-    struct MyCircuit<F: PrimeField, const NUM_BITS: usize, const RANGE: usize> {
-        num_bits: Value<u8>,
-        value: Value<Assigned<F>>,
+    fn without_witnesses(&self) -> Self {
+        Self::default()
     }
 
-    impl<F: PrimeField, const NUM_BITS: usize, const RANGE: usize> Circuit<F>
-        for MyCircuit<F, NUM_BITS, RANGE>
-    {
-        type Config = RangeCheckConfig<F, NUM_BITS, RANGE>;
-        type FloorPlanner = V1;
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let num_bits = meta.advice_column();
+        let value = meta.advice_column();
+        RangeCheckConfig::configure(meta, num_bits, value)
+    }
 
-        fn without_witnesses(&self) -> Self {
-            Self::default()
-        }
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<F>,
+    ) -> Result<(), Error> {
+        config.table.load(&mut layouter)?;
 
-        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
-            let num_bits = meta.advice_column();
-            let value = meta.advice_column();
-            RangeCheckConfig::configure(meta, num_bits, value)
-        }
+        config.assign(
+            layouter.namespace(|| "Assign value"),
+            self.num_bits,
+            self.value,
+        )?;
 
-        fn synthesize(
-            &self,
-            config: Self::Config,
-            mut layouter: impl Layouter<F>,
-        ) -> Result<(), Error> {
-            config.table.load(&mut layouter)?;
+        Ok(())
+    }
+}
 
-            config.assign(
-                layouter.namespace(|| "Assign value"),
-                self.num_bits,
-                self.value,
-            )?;
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
 
-            Ok(())
-        }
-    }
+    use super::*;
+    use crate::{proof::prove_and_verify, rows::min_k};
 
     #[test]
     fn test_range_check_3() {
-        let k = 9;
         const NUM_BITS: usize = 8;
         const RANGE: usize = 256; // 8-bit value
+        let k = min_k(&RangeCheckCircuit::<Fp, NUM_BITS, RANGE>::default()).unwrap();
 
         // Successful cases
         for num_bits in 1u8..=NUM_BITS.try_into().unwrap() {
             for value in (1 << (num_bits - 1))..(1 << num_bits) {
-                let circuit = MyCircuit::<Fp, NUM_BITS, RANGE> {
+                let circuit = RangeCheckCircuit::<Fp, NUM_BITS, RANGE> {
                     num_bits: Value::known(num_bits),
                     value: Value::known(Fp::from(value as u64).into()),
                 };
@@ -200,6 +212,26 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_range_check_3_real_prover() {
+        const NUM_BITS: usize = 8;
+        const RANGE: usize = 256; // 8-bit value
+        let k = min_k(&RangeCheckCircuit::<Fp, NUM_BITS, RANGE>::default()).unwrap();
+
+        let circuit = RangeCheckCircuit::<Fp, NUM_BITS, RANGE> {
+            num_bits: Value::known(5),
+            value: Value::known(Fp::from(20).into()),
+        };
+        prove_and_verify(k, circuit, &[]).unwrap();
+
+        // 20 is a 5-bit value, tagging it as 4 bits must fail
+        let circuit = RangeCheckCircuit::<Fp, NUM_BITS, RANGE> {
+            num_bits: Value::known(4),
+            value: Value::known(Fp::from(20).into()),
+        };
+        assert!(prove_and_verify(k, circuit, &[]).is_err());
+    }
+
     // $ cargo test --release --all-features print_range_check_3
     #[cfg(feature = "dev-graph")]
     #[test]
@@ -212,12 +244,12 @@ mod tests {
             .titled("Range Check 3 Layout", ("sans-serif", 60))
             .unwrap();
 
-        let circuit = MyCircuit::<Fp, 8, 256> {
+        let circuit = RangeCheckCircuit::<Fp, 8, 256> {
             num_bits: Value::unknown(),
             value: Value::unknown(),
         };
         halo2_proofs::dev::CircuitLayout::default()
-            .render(9, &circuit, &root)
+            .render(min_k(&circuit).unwrap(), &circuit, &root)
             .unwrap();
     }
 }
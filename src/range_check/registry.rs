@@ -0,0 +1,225 @@
+use std::marker::PhantomData;
+
+use ff::PrimeField;
+use halo2_proofs::{
+    circuit::Layouter,
+    plonk::{ConstraintSystem, Error, TableColumn},
+};
+
+use crate::range_check::{example2::table::load_range, example3::table::load_tagged};
+
+// example2, example3 and decompose_range_check each configure and load their own table.
+// A circuit combining them takes the tables from one registry instead, which hands out
+// the columns of each (kind, size) once and loads every table once:
+//
+//   let mut tables = TableRegistry::default();
+//   let a = example2::table::RangeTableConfig::<F, 256>::shared(meta, &mut tables);
+//   let b = WindowTableConfig::<F, 8>::shared(meta, &mut tables);   // same column as a
+//   ...
+//   tables.load(&mut layouter)?;   // in synthesize, instead of `a.load` and `b.load`
+
+/// What a table holds, for `size` rows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TableKind {
+    /// `value` in 0..size, as in example2 and decompose_range_check.
+    Range,
+    /// `(num_bits, value)` for the values of exactly `num_bits` bits, `size` being
+    /// 2^NUM_BITS, as in example3.
+    Tagged,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct TableRegistry<F: PrimeField> {
+    // in order of the first request, so keygen and proving lay the tables out the same way
+    tables: Vec<(TableKind, usize, Vec<TableColumn>)>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField> TableRegistry<F> {
+    /// The columns of the (kind, size) table, configured on the first request.
+    pub fn columns(
+        &mut self,
+        meta: &mut ConstraintSystem<F>,
+        kind: TableKind,
+        size: usize,
+    ) -> Vec<TableColumn> {
+        if let Some((.., columns)) = self
+            .tables
+            .iter()
+            .find(|(k, s, _)| *k == kind && *s == size)
+        {
+            return columns.clone();
+        }
+
+        let width = match kind {
+            TableKind::Range => {
+                assert!(size > 0, "a range table has at least one row");
+                1
+            }
+            TableKind::Tagged => {
+                assert!(
+                    size > 1 && size.is_power_of_two(),
+                    "a tagged table has 2^NUM_BITS rows"
+                );
+                2
+            }
+        };
+        let columns: Vec<_> = (0..width).map(|_| meta.lookup_table_column()).collect();
+        self.tables.push((kind, size, columns.clone()));
+        columns
+    }
+
+    /// The column of the values 0..size.
+    pub fn range(&mut self, meta: &mut ConstraintSystem<F>, size: usize) -> TableColumn {
+        self.columns(meta, TableKind::Range, size)[0]
+    }
+
+    /// The `[num_bits, value]` columns of the table tagged 1..=num_bits.
+    pub fn tagged(&mut self, meta: &mut ConstraintSystem<F>, num_bits: usize) -> [TableColumn; 2] {
+        let columns = self.columns(meta, TableKind::Tagged, 1 << num_bits);
+        [columns[0], columns[1]]
+    }
+
+    /// Loads every table handed out, once, with the same rows as the tables' own `load`.
+    pub fn load(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        for (kind, size, columns) in &self.tables {
+            match kind {
+                TableKind::Range => load_range(layouter, columns[0], *size)?,
+                TableKind::Tagged => {
+                    let num_bits = size.trailing_zeros() as usize;
+                    load_tagged(layouter, columns[0], columns[1], num_bits)?
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        range_check::{
+            decompose_range_check::{
+                table::WindowTableConfig, RunningSumDecomposeChip, RunningSumDecomposeConfig,
+            },
+            example2, example3,
+        },
+        rows::{layout_report, mock_verify},
+    };
+    use halo2_proofs::{
+        circuit::{SimpleFloorPlanner, Value},
+        pasta::Fp,
+        plonk::{Assigned, Circuit},
+    };
+
+    // the three chips of the request, all looking up 8-bit values
+    #[derive(Default)]
+    struct SharedCircuit {
+        lookup: Value<u64>,
+        tagged: Value<(u8, u64)>,
+        decompose: Value<u64>,
+    }
+
+    #[derive(Clone)]
+    struct SharedConfig {
+        lookup: example2::RangeCheckConfig<Fp, 8, 256>,
+        tagged: example3::RangeCheckConfig<Fp, 8, 256>,
+        decompose: RunningSumDecomposeConfig<Fp, 8, 2>,
+        tables: TableRegistry<Fp>,
+    }
+
+    impl Circuit<Fp> for SharedCircuit {
+        type Config = SharedConfig;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let mut tables = TableRegistry::default();
+            let [a, b, c, d, e] = [(); 5].map(|_| meta.advice_column());
+
+            let table = example2::table::RangeTableConfig::shared(meta, &mut tables);
+            let lookup = example2::RangeCheckConfig::configure_with_table(meta, a, table);
+            let table = example3::table::RangeTableConfig::shared(meta, &mut tables);
+            let tagged = example3::RangeCheckConfig::configure_with_table(meta, b, c, table);
+            let table = WindowTableConfig::shared(meta, &mut tables);
+            let decompose = RunningSumDecomposeChip::configure(meta, [d, e], table);
+
+            SharedConfig {
+                lookup,
+                tagged,
+                decompose,
+                tables,
+            }
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            config.tables.load(&mut layouter)?;
+
+            let lookup = self.lookup.map(|v| Assigned::from(Fp::from(v)));
+            config
+                .lookup
+                .assign_lookup(layouter.namespace(|| "lookup"), lookup)?;
+
+            let num_bits = self.tagged.map(|(bits, _)| bits);
+            let value = self.tagged.map(|(_, v)| Assigned::from(Fp::from(v)));
+            config
+                .tagged
+                .assign(layouter.namespace(|| "tagged"), num_bits, value)?;
+
+            let chip = RunningSumDecomposeChip::construct(config.decompose);
+            chip.witness_decompose(
+                layouter.namespace(|| "decompose"),
+                self.decompose.map(Fp::from),
+            )?;
+            Ok(())
+        }
+    }
+
+    fn check(lookup: u64, tagged: (u8, u64), decompose: u64) -> bool {
+        let circuit = SharedCircuit {
+            lookup: Value::known(lookup),
+            tagged: Value::known(tagged),
+            decompose: Value::known(decompose),
+        };
+        mock_verify(&circuit, vec![])
+    }
+
+    #[test]
+    fn test_shared_tables() {
+        // the 0..256 column is configured once, next to the two tagged columns
+        let report = layout_report(&SharedCircuit::default()).unwrap();
+        assert_eq!(report.fixed_columns, 3);
+
+        assert!(check(0, (1, 0), 0));
+        assert!(check(255, (8, 200), 65535));
+        assert!(check(17, (3, 5), 256));
+
+        assert!(!check(256, (8, 200), 0));
+        assert!(!check(0, (3, 200), 0));
+        assert!(!check(0, (8, 200), 65536));
+    }
+
+    #[test]
+    fn test_registry_reuses_columns() {
+        let mut meta = ConstraintSystem::<Fp>::default();
+        let mut tables = TableRegistry::default();
+
+        let a = tables.range(&mut meta, 256);
+        let b = tables.range(&mut meta, 256);
+        let c = tables.range(&mut meta, 1024);
+        let [tag, value] = tables.tagged(&mut meta, 8);
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+        assert_ne!(a, value);
+        assert_eq!(tables.tagged(&mut meta, 8), [tag, value]);
+        assert_eq!(meta.num_fixed_columns(), 4);
+    }
+}
@@ -0,0 +1,348 @@
+use ff::PrimeField;
+use halo2_proofs::{
+    circuit::{AssignedCell, Layouter, Region, Value},
+    plonk::{Advice, Column, ConstraintSystem, Constraints, Error, Expression, Selector},
+    poly::Rotation,
+};
+
+use super::{
+    bounded::fits_in_bits,
+    decompose_range_check::{low_bits, table::WindowTableConfig},
+};
+use crate::boolean::bool_check;
+
+// v in [-2^(K-1), 2^(K-1)), negative values being p - |v|, by offsetting into the
+// unsigned table of the K-bit words:
+//
+//   u = v + 2^(K-1)  in [0, 2^K)
+//
+// The top bit of u is 1 - sign, so the rest r = u - (1 - sign) · 2^(K-1) must be a
+// (K-1)-bit word, checked as 2r in the same table:
+//
+//   value | sign | abs | q_signed
+//
+//   q_signed · (v + 2^(K-1))                          ∈ table
+//   q_signed · 2 · (v + sign · 2^(K-1))               ∈ table
+//   sign · (1 - sign)                                 = 0
+//   abs - v · (1 - 2 · sign)                          = 0
+//
+// abs is in [0, 2^(K-1)], -2^(K-1) has no positive counterpart in K bits.
+
+#[derive(Debug, Clone)]
+pub struct SignedRangeCheckConfig<F: PrimeField, const K: usize> {
+    q_signed: Selector,
+    value: Column<Advice>,
+    sign: Column<Advice>,
+    abs: Column<Advice>,
+    table: WindowTableConfig<F, K>,
+}
+
+/// The cells of one signed range check.
+#[derive(Debug, Clone)]
+pub struct Signed<F: PrimeField> {
+    pub value: AssignedCell<F, F>,
+    /// 1 for a negative value, 0 otherwise.
+    pub sign: AssignedCell<F, F>,
+    pub abs: AssignedCell<F, F>,
+}
+
+impl<F: PrimeField> Signed<F> {
+    pub fn to_i64(&self) -> Value<i64> {
+        self.value
+            .value()
+            .map(|value| field_to_i64(*value).expect("a range-checked value fits in i64"))
+    }
+}
+
+/// `v` as a field element, negative values being `p - |v|`.
+pub fn i64_to_field<F: PrimeField>(v: i64) -> F {
+    if v < 0 {
+        -F::from(v.unsigned_abs())
+    } else {
+        F::from(v as u64)
+    }
+}
+
+/// The inverse of [`i64_to_field`], `None` for elements outside the i64 range.
+pub fn field_to_i64<F: PrimeField>(value: F) -> Option<i64> {
+    if fits_in_bits(value, 63) {
+        Some(low_bits(value, 63) as i64)
+    } else if fits_in_bits(-value - F::ONE, 63) {
+        // -v - 1 in [0, 2^63) for v in [-2^63, 0)
+        Some(-(low_bits(-value - F::ONE, 63) as i64) - 1)
+    } else {
+        None
+    }
+}
+
+pub struct SignedRangeCheckChip<F: PrimeField, const K: usize> {
+    config: SignedRangeCheckConfig<F, K>,
+}
+
+impl<F: PrimeField, const K: usize> SignedRangeCheckChip<F, K> {
+    pub fn construct(config: SignedRangeCheckConfig<F, K>) -> Self {
+        Self { config }
+    }
+
+    /// `table` is only configured here, the circuit owning it loads it once.
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        [value, sign, abs]: [Column<Advice>; 3],
+        table: WindowTableConfig<F, K>,
+    ) -> SignedRangeCheckConfig<F, K> {
+        assert!((1..=16).contains(&K), "the table has 2^K rows");
+        let q_signed = meta.complex_selector();
+        for column in [value, sign, abs] {
+            meta.enable_equality(column);
+        }
+
+        meta.lookup(|meta| {
+            let q = meta.query_selector(q_signed);
+            let v = meta.query_advice(value, Rotation::cur());
+            vec![(q * (v + Expression::Constant(Self::half())), table.value)]
+        });
+
+        meta.lookup(|meta| {
+            let q = meta.query_selector(q_signed);
+            let v = meta.query_advice(value, Rotation::cur());
+            let sign = meta.query_advice(sign, Rotation::cur());
+            let rest = v + sign * Expression::Constant(Self::half());
+            vec![(q * Expression::Constant(F::from(2)) * rest, table.value)]
+        });
+
+        meta.create_gate("signed", |meta| {
+            let q = meta.query_selector(q_signed);
+            let v = meta.query_advice(value, Rotation::cur());
+            let sign = meta.query_advice(sign, Rotation::cur());
+            let abs = meta.query_advice(abs, Rotation::cur());
+            let one = Expression::Constant(F::ONE);
+            let two = Expression::Constant(F::from(2));
+
+            Constraints::with_selector(
+                q,
+                [
+                    ("sign is boolean", bool_check(sign.clone())),
+                    ("abs", abs - v * (one - two * sign)),
+                ],
+            )
+        });
+
+        SignedRangeCheckConfig {
+            q_signed,
+            value,
+            sign,
+            abs,
+            table,
+        }
+    }
+
+    // 2^(K-1)
+    fn half() -> F {
+        F::from(1 << (K - 1))
+    }
+
+    pub fn witness_check(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: Value<F>,
+    ) -> Result<Signed<F>, Error> {
+        layouter.assign_region(
+            || "signed range check",
+            |mut region| {
+                let v = region.assign_advice(|| "value", self.config.value, 0, || value)?;
+                self.check(&mut region, v)
+            },
+        )
+    }
+
+    pub fn witness_i64(
+        &self,
+        layouter: impl Layouter<F>,
+        value: Value<i64>,
+    ) -> Result<Signed<F>, Error> {
+        self.witness_check(layouter, value.map(i64_to_field))
+    }
+
+    pub fn copy_check(
+        &self,
+        mut layouter: impl Layouter<F>,
+        value: &AssignedCell<F, F>,
+    ) -> Result<Signed<F>, Error> {
+        layouter.assign_region(
+            || "signed range check",
+            |mut region| {
+                let v = value.copy_advice(|| "value", &mut region, self.config.value, 0)?;
+                self.check(&mut region, v)
+            },
+        )
+    }
+
+    fn check(
+        &self,
+        region: &mut Region<'_, F>,
+        value: AssignedCell<F, F>,
+    ) -> Result<Signed<F>, Error> {
+        let config = &self.config;
+        config.q_signed.enable(region, 0)?;
+
+        // the top bit of u = v + 2^(K-1) is 1 - sign
+        let v = value.value().copied();
+        let sign = v.map(|v| {
+            let top = low_bits(v + Self::half(), K) >> (K - 1);
+            F::from(1 - top)
+        });
+        let abs = v * sign.map(|sign| F::ONE - sign.double());
+
+        let sign = region.assign_advice(|| "sign", config.sign, 0, || sign)?;
+        let abs = region.assign_advice(|| "abs", config.abs, 0, || abs)?;
+        Ok(Signed { value, sign, abs })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rows::{assert_instance_bound, min_k, mock_verify};
+    use halo2_proofs::{
+        circuit::SimpleFloorPlanner,
+        dev::MockProver,
+        pasta::Fp,
+        plonk::{Circuit, Instance},
+    };
+
+    // instance: [value, sign, abs]
+    #[derive(Default)]
+    struct SignedCircuit {
+        value: Value<i64>,
+    }
+
+    impl Circuit<Fp> for SignedCircuit {
+        type Config = (SignedRangeCheckConfig<Fp, 8>, Column<Instance>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let columns = [(); 3].map(|_| meta.advice_column());
+            let table = WindowTableConfig::configure(meta);
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+            (
+                SignedRangeCheckChip::configure(meta, columns, table),
+                instance,
+            )
+        }
+
+        fn synthesize(
+            &self,
+            (config, instance): Self::Config,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            config.table.load(&mut layouter)?;
+            let chip = SignedRangeCheckChip::construct(config);
+            let signed = chip.witness_i64(layouter.namespace(|| "v"), self.value)?;
+            for (row, cell) in [signed.value, signed.sign, signed.abs].iter().enumerate() {
+                layouter.constrain_instance(cell.cell(), instance, row)?;
+            }
+            Ok(())
+        }
+    }
+
+    fn instance(v: i64) -> Vec<Fp> {
+        vec![
+            i64_to_field(v),
+            Fp::from((v < 0) as u64),
+            Fp::from(v.unsigned_abs()),
+        ]
+    }
+
+    #[test]
+    fn test_signed_range_check() {
+        for v in -128..128 {
+            let circuit = SignedCircuit {
+                value: Value::known(v),
+            };
+            // the sign and abs are bound to the value
+            assert_instance_bound(&circuit, &instance(v));
+        }
+
+        for v in [-129, 128, 255, 256, -256, 1000, i64::MIN, i64::MAX] {
+            let circuit = SignedCircuit {
+                value: Value::known(v),
+            };
+            assert!(!mock_verify(&circuit, vec![instance(v)]), "{v}");
+        }
+    }
+
+    // one row with a chosen sign, abs following from it
+    struct ForgedCircuit {
+        value: i64,
+        sign: bool,
+    }
+
+    impl Circuit<Fp> for ForgedCircuit {
+        type Config = SignedRangeCheckConfig<Fp, 8>;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self { ..*self }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            SignedCircuit::configure(meta).0
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<Fp>,
+        ) -> Result<(), Error> {
+            config.table.load(&mut layouter)?;
+            let v = i64_to_field::<Fp>(self.value);
+            let sign = Fp::from(self.sign as u64);
+            let abs = v * (Fp::one() - Fp::from(2) * sign);
+
+            layouter.assign_region(
+                || "forged",
+                |mut region| {
+                    config.q_signed.enable(&mut region, 0)?;
+                    for (name, column, value) in [
+                        ("value", config.value, v),
+                        ("sign", config.sign, sign),
+                        ("abs", config.abs, abs),
+                    ] {
+                        region.assign_advice(|| name, column, 0, || Value::known(value))?;
+                    }
+                    Ok(())
+                },
+            )
+        }
+    }
+
+    #[test]
+    fn test_signed_forged_sign() {
+        let k = min_k(&SignedCircuit::default()).unwrap();
+        for value in [-128, -5, -1, 0, 1, 5, 127] {
+            for sign in [false, true] {
+                let prover = MockProver::run(k, &ForgedCircuit { value, sign }, vec![]).unwrap();
+                assert_eq!(
+                    prover.verify().is_ok(),
+                    sign == (value < 0),
+                    "{value} with sign = {sign}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_i64_conversions() {
+        for v in [0, 1, -1, 127, -128, i64::MAX, i64::MIN, i64::MIN + 1] {
+            assert_eq!(field_to_i64(i64_to_field::<Fp>(v)), Some(v), "{v}");
+        }
+        assert_eq!(field_to_i64(Fp::from(1 << 63)), None);
+        assert_eq!(field_to_i64(-Fp::from(1 << 63) - Fp::one()), None);
+        assert_eq!(field_to_i64(Fp::from(u64::MAX) * Fp::from(u64::MAX)), None);
+    }
+}
@@ -0,0 +1,401 @@
+use std::fmt;
+
+use ff::{Field, PrimeField};
+use halo2_proofs::{
+    circuit::Value,
+    plonk::{
+        Advice, Any, Assigned, Assignment, Circuit, Column, ConstraintSystem, Error, Fixed,
+        FloorPlanner, Instance, Selector,
+    },
+};
+#[cfg(test)]
+use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+// Measures how many rows a circuit really uses, so the tests, the CLI and the artifacts
+// never have to guess `k`.
+//
+// The circuit is synthesized once against `RowCounter`, which records the highest row
+// touched by any assignment: region cells, selectors, copy constraints (including the
+// instance rows used by `constrain_instance`), lookup table rows and the constants the
+// floor planner places. On top of that halo2 reserves `blinding_factors() + 1` rows at the
+// end of every column, and needs at least `minimum_rows()` rows in total:
+//
+//   2^k >= max(rows + blinding_factors + 1, minimum_rows)
+
+/// The row use of a circuit, as measured by [`row_usage`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RowUsage {
+    /// Rows touched by the layout, i.e. one past the highest assigned row.
+    pub rows: usize,
+    /// Rows halo2 reserves for blinding (plus one for the last row).
+    pub blinding_factors: usize,
+    /// Smallest circuit size the constraint system supports, whatever `rows` is.
+    pub minimum_rows: usize,
+    /// Largest `k` the field supports for this constraint system's degree.
+    pub max_k: u32,
+}
+
+impl RowUsage {
+    /// Rows needed in total: the used rows followed by the reserved ones.
+    pub fn required_rows(&self) -> usize {
+        (self.rows + self.blinding_factors + 1).max(self.minimum_rows)
+    }
+
+    /// The smallest `k` with `2^k >= required_rows()`.
+    pub fn min_k(&self) -> Result<u32, KError> {
+        let required = self.required_rows();
+        let k = required.next_power_of_two().trailing_zeros();
+        if k > self.max_k {
+            return Err(KError::TooManyRows {
+                required,
+                max_k: self.max_k,
+            });
+        }
+        Ok(k)
+    }
+}
+
+#[derive(Debug)]
+pub enum KError {
+    /// The circuit failed to synthesize, so its rows could not be counted.
+    Synthesis(Error),
+    /// The circuit needs more rows than any supported `k` provides.
+    TooManyRows { required: usize, max_k: u32 },
+}
+
+impl fmt::Display for KError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KError::Synthesis(e) => write!(f, "cannot count the circuit's rows: {e}"),
+            KError::TooManyRows { required, max_k } => write!(
+                f,
+                "the circuit needs {required} rows, more than 2^{max_k} (the largest supported k)"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for KError {}
+
+impl From<Error> for KError {
+    fn from(e: Error) -> Self {
+        KError::Synthesis(e)
+    }
+}
+
+/// Synthesizes `circuit` once and reports how many rows it uses.
+pub fn row_usage<F: PrimeField, C: Circuit<F>>(circuit: &C) -> Result<RowUsage, Error> {
+    measure(circuit).map(|(usage, _)| usage)
+}
+
+/// The smallest `k` `circuit` can be run (and proven) with.
+pub fn min_k<F: PrimeField, C: Circuit<F>>(circuit: &C) -> Result<u32, KError> {
+    row_usage(circuit)?.min_k()
+}
+
+/// Whether the MockProver accepts `circuit` for `instances`, run at its [`min_k`].
+#[cfg(test)]
+pub(crate) fn mock_verify<C: Circuit<Fp>>(circuit: &C, instances: Vec<Vec<Fp>>) -> bool {
+    let k = min_k(circuit).unwrap();
+    MockProver::run(k, circuit, instances)
+        .unwrap()
+        .verify()
+        .is_ok()
+}
+
+/// Asserts that `circuit` is satisfied for its single instance column `instance`, and
+/// that every row of the column is bound: adding one to any of them is rejected.
+#[cfg(test)]
+#[track_caller]
+pub(crate) fn assert_instance_bound<C: Circuit<Fp>>(circuit: &C, instance: &[Fp]) {
+    assert_rows_bound(circuit, instance, |v| v + Fp::one());
+}
+
+/// [`assert_instance_bound`] for boolean outputs: each row is flipped to the other bit
+/// instead, as adding one would turn a true bit into 2, which no boolean output can be.
+#[cfg(test)]
+#[track_caller]
+pub(crate) fn assert_bits_bound<C: Circuit<Fp>>(circuit: &C, instance: &[Fp]) {
+    assert_rows_bound(circuit, instance, |v| Fp::one() - v);
+}
+
+#[cfg(test)]
+#[track_caller]
+fn assert_rows_bound<C: Circuit<Fp>>(circuit: &C, instance: &[Fp], wrong: impl Fn(Fp) -> Fp) {
+    let k = min_k(circuit).unwrap();
+    MockProver::run(k, circuit, vec![instance.to_vec()])
+        .unwrap()
+        .assert_satisfied();
+
+    for row in 0..instance.len() {
+        let mut instance = instance.to_vec();
+        instance[row] = wrong(instance[row]);
+        assert!(
+            !mock_verify(circuit, vec![instance]),
+            "instance row {row} accepted a wrong value"
+        );
+    }
+}
+
+/// The rows and columns a circuit uses, e.g. to compare different layouts of the
+/// same computation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LayoutReport {
+    pub rows: usize,
+    pub k: u32,
+    pub advice_columns: usize,
+    pub fixed_columns: usize,
+    pub instance_columns: usize,
+    pub selectors: usize,
+}
+
+impl fmt::Display for LayoutReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} rows (k = {}), {} advice, {} fixed, {} instance columns, {} selectors",
+            self.rows,
+            self.k,
+            self.advice_columns,
+            self.fixed_columns,
+            self.instance_columns,
+            self.selectors
+        )
+    }
+}
+
+/// Measures `circuit` like [`min_k`] and adds the columns it configures.
+pub fn layout_report<F: PrimeField, C: Circuit<F>>(circuit: &C) -> Result<LayoutReport, KError> {
+    let (usage, cs) = measure(circuit)?;
+    Ok(LayoutReport {
+        rows: usage.rows,
+        k: usage.min_k()?,
+        advice_columns: cs.num_advice_columns(),
+        fixed_columns: cs.num_fixed_columns(),
+        instance_columns: cs.num_instance_columns(),
+        selectors: cs.num_selectors(),
+    })
+}
+
+fn measure<F: PrimeField, C: Circuit<F>>(
+    circuit: &C,
+) -> Result<(RowUsage, ConstraintSystem<F>), Error> {
+    let mut cs = ConstraintSystem::default();
+    let config = C::configure(&mut cs);
+
+    let mut counter = RowCounter::default();
+    C::FloorPlanner::synthesize(&mut counter, circuit, config, cs.constants().clone())?;
+
+    // The quotient polynomial is committed over an extended domain of
+    // 2^k * (degree - 1) points, which must fit into the 2^S roots of unity of `F`.
+    let quotient_degree = cs.degree().max(2) - 1;
+    let extension = quotient_degree.next_power_of_two().trailing_zeros();
+
+    let usage = RowUsage {
+        rows: counter.rows,
+        blinding_factors: cs.blinding_factors(),
+        minimum_rows: cs.minimum_rows(),
+        max_k: F::S - extension,
+    };
+    Ok((usage, cs))
+}
+
+#[derive(Default)]
+struct RowCounter {
+    rows: usize,
+}
+
+impl RowCounter {
+    fn touch(&mut self, row: usize) {
+        self.rows = self.rows.max(row + 1);
+    }
+}
+
+impl<F: Field> Assignment<F> for RowCounter {
+    fn enter_region<NR, N>(&mut self, _: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+    }
+
+    fn exit_region(&mut self) {}
+
+    fn enable_selector<A, AR>(&mut self, _: A, _: &Selector, row: usize) -> Result<(), Error>
+    where
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.touch(row);
+        Ok(())
+    }
+
+    fn query_instance(&self, _: Column<Instance>, _: usize) -> Result<Value<F>, Error> {
+        Ok(Value::unknown())
+    }
+
+    fn assign_advice<V, VR, A, AR>(
+        &mut self,
+        _: A,
+        _: Column<Advice>,
+        row: usize,
+        _: V,
+    ) -> Result<(), Error>
+    where
+        V: FnOnce() -> Value<VR>,
+        VR: Into<Assigned<F>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.touch(row);
+        Ok(())
+    }
+
+    fn assign_fixed<V, VR, A, AR>(
+        &mut self,
+        _: A,
+        _: Column<Fixed>,
+        row: usize,
+        _: V,
+    ) -> Result<(), Error>
+    where
+        V: FnOnce() -> Value<VR>,
+        VR: Into<Assigned<F>>,
+        A: FnOnce() -> AR,
+        AR: Into<String>,
+    {
+        self.touch(row);
+        Ok(())
+    }
+
+    fn copy(
+        &mut self,
+        _: Column<Any>,
+        left_row: usize,
+        _: Column<Any>,
+        right_row: usize,
+    ) -> Result<(), Error> {
+        self.touch(left_row);
+        self.touch(right_row);
+        Ok(())
+    }
+
+    // Lookup tables pad their remaining rows with the default value; that fills
+    // whatever is left of the column and uses no new rows.
+    fn fill_from_row(
+        &mut self,
+        _: Column<Fixed>,
+        _: usize,
+        _: Value<Assigned<F>>,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn push_namespace<NR, N>(&mut self, _: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+    }
+
+    fn pop_namespace(&mut self, _: Option<String>) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{circuit::Value, dev::MockProver, pasta::Fp};
+
+    use super::*;
+    use crate::{
+        fibonacci::{example1, example2},
+        range_check::example2 as range_check2,
+    };
+
+    #[test]
+    fn test_min_k_fibonacci() {
+        // example1 uses one row per step, with `f(0)` and `f(1)` sharing the first row;
+        // exposing the output always touches instance row 2
+        for (n, rows, k) in [
+            (2, 3, 4),
+            (9, 8, 4),
+            (11, 10, 4),
+            (12, 11, 5),
+            (27, 26, 5),
+            (28, 27, 6),
+        ] {
+            let circuit = example1::FibonacciCircuit::<Fp>::new(n);
+            let usage = row_usage(&circuit).unwrap();
+            assert_eq!(usage.rows, rows);
+            assert_eq!(usage.blinding_factors, 5);
+            assert_eq!(min_k(&circuit).unwrap(), k);
+        }
+
+        // example2 writes `n + 1` terms into a single column
+        let circuit = example2::FibonacciCircuit::<Fp>::new(9);
+        assert_eq!(row_usage(&circuit).unwrap().rows, 10);
+        assert_eq!(min_k(&circuit).unwrap(), 4);
+    }
+
+    #[test]
+    fn test_min_k_counts_lookup_tables() {
+        // the 256-row lookup table dominates the two single-row regions
+        let circuit = range_check2::RangeCheckCircuit::<Fp, 8, 256> {
+            simple_value: Value::known(Fp::from(3).into()),
+            lookup_value: Value::known(Fp::from(200).into()),
+        };
+        assert_eq!(row_usage(&circuit).unwrap().rows, 256);
+        assert_eq!(min_k(&circuit).unwrap(), 9);
+    }
+
+    #[test]
+    fn test_min_k_is_tight() {
+        let circuit = example1::FibonacciCircuit::<Fp>::new(27);
+        let k = min_k(&circuit).unwrap();
+        let instance = vec![
+            Fp::one(),
+            Fp::one(),
+            example1::nth_term(Fp::one(), Fp::one(), 27),
+        ];
+
+        let prover = MockProver::run(k, &circuit, vec![instance.clone()]).unwrap();
+        prover.assert_satisfied();
+        assert!(matches!(
+            MockProver::run(k - 1, &circuit, vec![instance]),
+            Err(Error::NotEnoughRowsAvailable { .. })
+        ));
+
+        let circuit = range_check2::RangeCheckCircuit::<Fp, 8, 256> {
+            simple_value: Value::known(Fp::from(3).into()),
+            lookup_value: Value::known(Fp::from(200).into()),
+        };
+        let k = min_k(&circuit).unwrap();
+        MockProver::run(k, &circuit, vec![])
+            .unwrap()
+            .assert_satisfied();
+        assert!(matches!(
+            MockProver::run(k - 1, &circuit, vec![]),
+            Err(Error::NotEnoughRowsAvailable { .. })
+        ));
+    }
+
+    #[test]
+    fn test_min_k_errors() {
+        // example2 cannot lay out fewer than 3 steps
+        let circuit = example2::FibonacciCircuit::<Fp>::new(2);
+        assert!(matches!(
+            min_k(&circuit),
+            Err(KError::Synthesis(Error::Synthesis))
+        ));
+
+        let usage = RowUsage {
+            rows: 1 << 30,
+            blinding_factors: 5,
+            minimum_rows: 8,
+            max_k: 30,
+        };
+        assert!(matches!(
+            usage.min_k(),
+            Err(KError::TooManyRows { required, max_k: 30 }) if required == (1 << 30) + 6
+        ));
+    }
+}
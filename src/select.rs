@@ -0,0 +1,457 @@
+use std::marker::PhantomData;
+
+use ff::PrimeField;
+use halo2_proofs::{circuit::*, plonk::*, poly::Rotation};
+
+use crate::boolean::bool_check;
+
+// out = cond ? x : y, for a boolean cond:
+//
+//   cond | x | y | out | q_select
+//
+//   cond · (1 - cond) = 0
+//   out = y + cond · (x - y)
+//
+// The example3 if-else gate is this select with cond = is_equal(a, b).
+
+#[derive(Debug, Clone)]
+pub struct SelectConfig {
+    q_select: Selector,
+    cond: Column<Advice>,
+    x: Column<Advice>,
+    y: Column<Advice>,
+    out: Column<Advice>,
+}
+
+pub struct SelectChip<F: PrimeField> {
+    config: SelectConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField> SelectChip<F> {
+    pub fn construct(config: SelectConfig) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        [cond, x, y, out]: [Column<Advice>; 4],
+    ) -> SelectConfig {
+        let q_select = meta.selector();
+        for column in [cond, x, y, out] {
+            meta.enable_equality(column);
+        }
+
+        meta.create_gate("select", |meta| {
+            let q = meta.query_selector(q_select);
+            let [cond, x, y, out] =
+                [cond, x, y, out].map(|column| meta.query_advice(column, Rotation::cur()));
+
+            Constraints::with_selector(
+                q,
+                [
+                    ("cond is boolean", bool_check(cond.clone())),
+                    ("out", out - (y.clone() + cond * (x - y))),
+                ],
+            )
+        });
+
+        SelectConfig {
+            q_select,
+            cond,
+            x,
+            y,
+            out,
+        }
+    }
+
+    /// Returns a cell holding `x` if `cond` is 1 and `y` if it is 0.
+    pub fn select(
+        &self,
+        mut layouter: impl Layouter<F>,
+        cond: &AssignedCell<F, F>,
+        x: &AssignedCell<F, F>,
+        y: &AssignedCell<F, F>,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        let config = &self.config;
+
+        layouter.assign_region(
+            || "select",
+            |mut region| {
+                config.q_select.enable(&mut region, 0)?;
+                let cond = cond.copy_advice(|| "cond", &mut region, config.cond, 0)?;
+                let x = x.copy_advice(|| "x", &mut region, config.x, 0)?;
+                let y = y.copy_advice(|| "y", &mut region, config.y, 0)?;
+
+                let out =
+                    y.value().copied() + cond.value().copied() * (x.value().copied() - y.value());
+                region.assign_advice(|| "out", config.out, 0, || out)
+            },
+        )
+    }
+}
+
+// Picks one of N inputs with a one-hot selector s:
+//
+//   input_0 | ... | input_{N-1} | s_0 | ... | s_{N-1} | index | out | q_mux
+//
+//   s_i · (1 - s_i) = 0
+//   Σ s_i           = 1
+//   index           = Σ i · s_i
+//   out             = Σ s_i · input_i
+//
+// The index is bound to the one-hot selector, so an index outside 0..N is unprovable.
+
+#[derive(Debug, Clone)]
+pub struct MuxConfig<const N: usize> {
+    q_mux: Selector,
+    inputs: [Column<Advice>; N],
+    one_hot: [Column<Advice>; N],
+    index: Column<Advice>,
+    out: Column<Advice>,
+}
+
+/// The cells of one mux row.
+#[derive(Debug, Clone)]
+pub struct Muxed<F: PrimeField, const N: usize> {
+    pub out: AssignedCell<F, F>,
+    pub index: AssignedCell<F, F>,
+    pub one_hot: [AssignedCell<F, F>; N],
+}
+
+pub struct MuxChip<F: PrimeField, const N: usize> {
+    config: MuxConfig<N>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: PrimeField, const N: usize> MuxChip<F, N> {
+    pub fn construct(config: MuxConfig<N>) -> Self {
+        Self {
+            config,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        inputs: [Column<Advice>; N],
+        one_hot: [Column<Advice>; N],
+        [index, out]: [Column<Advice>; 2],
+    ) -> MuxConfig<N> {
+        assert!(N > 0, "nothing to select from");
+        let q_mux = meta.selector();
+        for column in inputs.into_iter().chain(one_hot).chain([index, out]) {
+            meta.enable_equality(column);
+        }
+
+        meta.create_gate("mux", |meta| {
+            let q = meta.query_selector(q_mux);
+            let inputs = inputs.map(|column| meta.query_advice(column, Rotation::cur()));
+            let one_hot = one_hot.map(|column| meta.query_advice(column, Rotation::cur()));
+            let index = meta.query_advice(index, Rotation::cur());
+            let out = meta.query_advice(out, Rotation::cur());
+
+            let zero = Expression::Constant(F::ZERO);
+            let one = Expression::Constant(F::ONE);
+            let booleans: Vec<_> = one_hot.iter().map(|s| bool_check(s.clone())).collect();
+            let sum = one_hot.iter().fold(zero.clone(), |acc, s| acc + s.clone());
+            let weighted = one_hot
+                .iter()
+                .enumerate()
+                .fold(zero.clone(), |acc, (i, s)| {
+                    acc + Expression::Constant(F::from(i as u64)) * s.clone()
+                });
+            let selected = one_hot
+                .iter()
+                .zip(inputs.iter())
+                .fold(zero, |acc, (s, input)| acc + s.clone() * input.clone());
+
+            Constraints::with_selector(
+                q,
+                booleans
+                    .into_iter()
+                    .chain([sum - one, index - weighted, out - selected]),
+            )
+        });
+
+        MuxConfig {
+            q_mux,
+            inputs,
+            one_hot,
+            index,
+            out,
+        }
+    }
+
+    /// Selects `inputs[i]` for the one `one_hot[i]` that is 1.
+    pub fn mux_one_hot(
+        &self,
+        mut layouter: impl Layouter<F>,
+        inputs: &[AssignedCell<F, F>; N],
+        one_hot: &[AssignedCell<F, F>; N],
+    ) -> Result<Muxed<F, N>, Error> {
+        let config = &self.config;
+
+        layouter.assign_region(
+            || "mux one-hot",
+            |mut region| {
+                let one_hot = one_hot
+                    .iter()
+                    .zip(config.one_hot)
+                    .map(|(s, column)| s.copy_advice(|| "s", &mut region, column, 0))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let index = one_hot
+                    .iter()
+                    .enumerate()
+                    .fold(Value::known(F::ZERO), |acc, (i, s)| {
+                        acc + s.value().map(|s| *s * F::from(i as u64))
+                    });
+                let index = region.assign_advice(|| "index", config.index, 0, || index)?;
+
+                self.assign_row(&mut region, inputs, index, one_hot)
+            },
+        )
+    }
+
+    /// Selects `inputs[index]`, with the one-hot selector witnessed from `index`.
+    pub fn mux_index(
+        &self,
+        mut layouter: impl Layouter<F>,
+        inputs: &[AssignedCell<F, F>; N],
+        index: &AssignedCell<F, F>,
+    ) -> Result<Muxed<F, N>, Error> {
+        let config = &self.config;
+
+        layouter.assign_region(
+            || "mux index",
+            |mut region| {
+                let index = index.copy_advice(|| "index", &mut region, config.index, 0)?;
+                let one_hot = config
+                    .one_hot
+                    .iter()
+                    .enumerate()
+                    .map(|(i, &column)| {
+                        // an out-of-range index gets an all-zero selector, which fails Σ s_i = 1
+                        let s = index.value().map(|index| {
+                            if *index == F::from(i as u64) {
+                                F::ONE
+                            } else {
+                                F::ZERO
+                            }
+                        });
+                        region.assign_advice(|| "s", column, 0, || s)
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                self.assign_row(&mut region, inputs, index, one_hot)
+            },
+        )
+    }
+
+    // copies the inputs and assigns `out` for an already assigned selector and index
+    fn assign_row(
+        &self,
+        region: &mut Region<'_, F>,
+        inputs: &[AssignedCell<F, F>; N],
+        index: AssignedCell<F, F>,
+        one_hot: Vec<AssignedCell<F, F>>,
+    ) -> Result<Muxed<F, N>, Error> {
+        let config = &self.config;
+        config.q_mux.enable(region, 0)?;
+
+        let mut out = Value::known(F::ZERO);
+        for ((input, &column), s) in inputs.iter().zip(config.inputs.iter()).zip(&one_hot) {
+            let input = input.copy_advice(|| "input", region, column, 0)?;
+            out = out + s.value().copied() * input.value();
+        }
+        let out = region.assign_advice(|| "out", config.out, 0, || out)?;
+
+        Ok(Muxed {
+            out,
+            index,
+            one_hot: one_hot.try_into().unwrap(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        is_equal::{IsEqualChip, IsEqualConfig},
+        rows::{assert_instance_bound, min_k},
+    };
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    #[derive(Clone, Debug)]
+    struct TestConfig<F: PrimeField> {
+        input: Column<Advice>,
+        is_equal: IsEqualConfig<F>,
+        select: SelectConfig,
+        mux: MuxConfig<4>,
+        instance: Column<Instance>,
+    }
+
+    // instance: [if a == b {c} else {d}, inputs[index] by index, the same by one-hot]
+    #[derive(Default)]
+    struct SelectCircuit<F> {
+        a: Value<F>,
+        b: Value<F>,
+        c: Value<F>,
+        d: Value<F>,
+        index: Value<F>,
+        one_hot: [Value<F>; 4],
+    }
+
+    impl SelectCircuit<Fp> {
+        fn new((a, b, c, d): (u64, u64, u64, u64), index: u64, one_hot: [u64; 4]) -> Self {
+            Self {
+                a: Value::known(Fp::from(a)),
+                b: Value::known(Fp::from(b)),
+                c: Value::known(Fp::from(c)),
+                d: Value::known(Fp::from(d)),
+                index: Value::known(Fp::from(index)),
+                one_hot: one_hot.map(|s| Value::known(Fp::from(s))),
+            }
+        }
+    }
+
+    impl<F: PrimeField> Circuit<F> for SelectCircuit<F> {
+        type Config = TestConfig<F>;
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+            let input = meta.advice_column();
+            let instance = meta.instance_column();
+            meta.enable_equality(input);
+            meta.enable_equality(instance);
+
+            let is_equal_columns = [(); 4].map(|_| meta.advice_column());
+            let select_columns = [(); 4].map(|_| meta.advice_column());
+            let mux_inputs = [(); 4].map(|_| meta.advice_column());
+            let mux_one_hot = [(); 4].map(|_| meta.advice_column());
+            let mux_outputs = [(); 2].map(|_| meta.advice_column());
+
+            let is_equal = IsEqualChip::configure_cells(meta, is_equal_columns);
+            let select = SelectChip::configure(meta, select_columns);
+            let mux = MuxChip::configure(meta, mux_inputs, mux_one_hot, mux_outputs);
+
+            TestConfig {
+                input,
+                is_equal,
+                select,
+                mux,
+                instance,
+            }
+        }
+
+        fn synthesize(
+            &self,
+            config: Self::Config,
+            mut layouter: impl Layouter<F>,
+        ) -> Result<(), Error> {
+            let inputs = layouter.assign_region(
+                || "inputs",
+                |mut region| {
+                    [self.a, self.b, self.c, self.d, self.index]
+                        .into_iter()
+                        .chain(self.one_hot)
+                        .enumerate()
+                        .map(|(row, value)| {
+                            region.assign_advice(|| "input", config.input, row, || value)
+                        })
+                        .collect::<Result<Vec<_>, _>>()
+                },
+            )?;
+            let [a, b, c, d, index] = [0, 1, 2, 3, 4].map(|i| &inputs[i]);
+            let one_hot = [5, 6, 7, 8].map(|i| inputs[i].clone());
+            let abcd = [a, b, c, d].map(|cell| cell.clone());
+
+            // if a == b {c} else {d} from an equality test and a select
+            let cond = IsEqualChip::construct(config.is_equal).compare(
+                layouter.namespace(|| "a == b"),
+                a,
+                b,
+            )?;
+            let piecewise = SelectChip::construct(config.select).select(
+                layouter.namespace(|| "select"),
+                &cond,
+                c,
+                d,
+            )?;
+
+            let mux = MuxChip::construct(config.mux);
+            let by_index = mux.mux_index(layouter.namespace(|| "by index"), &abcd, index)?;
+            let by_one_hot =
+                mux.mux_one_hot(layouter.namespace(|| "by one-hot"), &abcd, &one_hot)?;
+
+            for (row, cell) in [piecewise, by_index.out, by_one_hot.out].iter().enumerate() {
+                layouter.constrain_instance(cell.cell(), config.instance, row)?;
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_select_and_mux() {
+        let (c, d) = (3, 4);
+
+        for (a, b, expected) in [(10, 10, c), (10, 11, d), (0, 5, d), (0, 0, c)] {
+            for index in 0..4 {
+                let mut one_hot = [0; 4];
+                one_hot[index] = 1;
+                let circuit = SelectCircuit::new((a, b, c, d), index as u64, one_hot);
+
+                let selected = [a, b, c, d][index];
+                let instance = vec![Fp::from(expected), Fp::from(selected), Fp::from(selected)];
+                assert_instance_bound(&circuit, &instance);
+            }
+        }
+    }
+
+    #[test]
+    fn test_mux_rejects_bad_selectors() {
+        let k = min_k(&SelectCircuit::<Fp>::default()).unwrap();
+        let signed = |s: i64| {
+            let s_abs = Fp::from(s.unsigned_abs());
+            if s < 0 {
+                -s_abs
+            } else {
+                s_abs
+            }
+        };
+
+        for (index, one_hot) in [
+            // index out of range, the witnessed selector is all zero
+            (4, [1, 0, 0, 0]),
+            // broken one-hot selectors next to a valid index
+            (0, [0, 0, 0, 0]),
+            (0, [1, 1, 0, 0]),
+            (0, [2, 0, 0, 0]),
+            (0, [1, 1, -1, 0]),
+        ] {
+            let mut circuit = SelectCircuit::new((1, 2, 3, 4), index, [0; 4]);
+            circuit.one_hot = one_hot.map(|s| Value::known(signed(s)));
+
+            // whatever the rows output, the mux gate rejects them
+            let by_index = Fp::from(if index < 4 { index + 1 } else { 0 });
+            let by_one_hot = (1..=4).zip(one_hot).fold(Fp::zero(), |acc, (input, s)| {
+                acc + Fp::from(input) * signed(s)
+            });
+            let instance = vec![Fp::from(4), by_index, by_one_hot];
+
+            let prover = MockProver::run(k, &circuit, vec![instance]).unwrap();
+            assert!(
+                prover.verify().is_err(),
+                "index {index}, one-hot {one_hot:?}"
+            );
+        }
+    }
+}